@@ -0,0 +1,12 @@
+use blockless_car::reader::BlockIterator;
+
+/// list the blocks of the car file from the stdin.
+/// e.g. ```cat test/carv1-basic.car | cargo run --example stream```
+fn main() {
+    let stdin = std::io::stdin();
+    let blocks = BlockIterator::new(stdin.lock()).unwrap();
+    for block in blocks {
+        let (cid, data) = block.unwrap();
+        println!("{cid} {}", data.len());
+    }
+}
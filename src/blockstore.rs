@@ -0,0 +1,101 @@
+use std::collections::HashSet;
+
+use cid::Cid;
+
+use crate::{error::CarError, reader::CarReader, section::identity_data};
+
+mod blockstore_rw;
+pub use blockstore_rw::CarBlockstoreRw;
+
+/// the read-only blockstore, mirrors the blockstore of go-car and boxo.
+/// the identity cids are always present, their data is inlined in the cid.
+pub trait Blockstore {
+    /// get the block data, `NotFound` if the block is not in the store.
+    fn get(&mut self, cid: &Cid) -> Result<Vec<u8>, CarError>;
+
+    fn has(&self, cid: &Cid) -> Result<bool, CarError>;
+
+    /// get the block size, the block data is not read.
+    fn get_size(&self, cid: &Cid) -> Result<usize, CarError>;
+
+    /// iterate all the cids in the store, the duplicated cids are yielded once.
+    fn all_keys(&self) -> Box<dyn Iterator<Item = Cid> + '_>;
+}
+
+impl<T> Blockstore for T
+where
+    T: CarReader,
+{
+    fn get(&mut self, cid: &Cid) -> Result<Vec<u8>, CarError> {
+        if !self.has(cid)? {
+            return Err(CarError::NotFound(format!("block {cid}")));
+        }
+        self.read_section_data(cid)
+    }
+
+    fn has(&self, cid: &Cid) -> Result<bool, CarError> {
+        Ok(identity_data(cid).is_some() || self.section(cid)?.is_some())
+    }
+
+    fn get_size(&self, cid: &Cid) -> Result<usize, CarError> {
+        if let Some(s) = self.section(cid)? {
+            return Ok(s.len());
+        }
+        identity_data(cid)
+            .map(<[u8]>::len)
+            .ok_or(CarError::NotFound(format!("block {cid}")))
+    }
+
+    fn all_keys(&self) -> Box<dyn Iterator<Item = Cid> + '_> {
+        let mut seen = HashSet::new();
+        Box::new(
+            self.sections()
+                .into_iter()
+                .map(|s| s.cid())
+                .filter(move |cid| seen.insert(*cid)),
+        )
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::{index::IndexCodec, reader, utils::wrap_v1};
+    use cid::multihash::Multihash;
+    use std::io::Cursor;
+
+    fn check(store: &mut impl Blockstore, expected: &[(Cid, Vec<u8>)]) {
+        assert_eq!(store.all_keys().count(), expected.len());
+        for (cid, data) in expected {
+            assert!(store.has(cid).unwrap());
+            assert_eq!(store.get_size(cid).unwrap(), data.len());
+            assert_eq!(&store.get(cid).unwrap(), data);
+        }
+        let missing = crate::utils::raw_cid(b"missing");
+        assert!(!store.has(&missing).unwrap());
+        assert!(matches!(store.get(&missing), Err(CarError::NotFound(_))));
+        assert!(store.get_size(&missing).is_err());
+        let identity = Cid::new_v1(0x55, Multihash::wrap(0x00, b"inline").unwrap());
+        assert!(store.has(&identity).unwrap());
+        assert_eq!(store.get_size(&identity).unwrap(), 6);
+        assert_eq!(store.get(&identity).unwrap(), b"inline");
+    }
+
+    #[test]
+    fn test_blockstore() {
+        let file = std::path::Path::new("test");
+        let file = file.join("carv1-basic.car");
+        let v1 = std::fs::read(file).unwrap();
+        let mut r = reader::new_v1(Cursor::new(&v1)).unwrap();
+        let expected: Vec<_> = r
+            .sections()
+            .iter()
+            .map(|s| (s.cid(), r.read_section_data(&s.cid()).unwrap()))
+            .collect();
+        check(&mut r, &expected);
+        check(&mut reader::new_lazy(Cursor::new(&v1)).unwrap(), &expected);
+        let mut v2 = Vec::new();
+        wrap_v1(Cursor::new(&v1), &mut v2, IndexCodec::MultihashIndexSorted).unwrap();
+        check(&mut reader::new_v2(Cursor::new(&v2)).unwrap(), &expected);
+    }
+}
@@ -0,0 +1,348 @@
+use std::{
+    collections::HashMap,
+    fs::{File, OpenOptions},
+    io::{self, Read, Seek, SeekFrom, Write},
+    path::Path,
+};
+
+use cid::Cid;
+
+use super::Blockstore;
+use crate::{
+    error::CarError,
+    header::{CarHeader, CarHeaderV1, CarHeaderV2},
+    reader::{scan_sections, ReaderOptions, ScanEnd},
+    section::{identity_data, Section},
+    writer::{CarWriter, CarWriterV2},
+};
+
+/// the read-write blockstore backed by the CARv2 file, the blocks are deduplicated by cid.
+/// the blocks are readable as soon as they are put, the index and the CARv2 header
+/// are written when the blockstore is finalized.
+/// the unfinalized file is resumed by `resume`, the index is rebuilt from the valid prefix
+/// of the data payload and the new blocks are appended after it.
+/// the identity cids are not stored, their data is inlined in the cid.
+pub struct CarBlockstoreRw<F> {
+    writer: CarWriterV2<F>,
+    data_offset: u64,
+    roots: Vec<Cid>,
+    /// the positions of the sections are relative to the data payload.
+    sections: Vec<Section>,
+    index: HashMap<Cid, usize>,
+}
+
+impl CarBlockstoreRw<File> {
+    /// open the CARv2 file, the file is created if it's empty, otherwise it's resumed
+    /// and the bytes after the valid sections are truncated.
+    pub fn open<P: AsRef<Path>>(path: P, roots: Vec<Cid>) -> Result<Self, CarError> {
+        let file = OpenOptions::new()
+            .read(true)
+            .write(true)
+            .create(true)
+            .truncate(false)
+            .open(path)?;
+        if file.metadata()?.len() == 0 {
+            return Self::create(file, roots);
+        }
+        let mut store = Self::resume(file, roots)?;
+        let w = store.writer.get_mut();
+        let end = w.stream_position()?;
+        w.set_len(end)?;
+        Ok(store)
+    }
+}
+
+impl<F> CarBlockstoreRw<F>
+where
+    F: Read + Write + Seek,
+{
+    /// create the blockstore, the headers are written at once so the file can be resumed.
+    pub fn create(inner: F, roots: Vec<Cid>) -> Result<Self, CarError> {
+        let header = CarHeader::V2(CarHeaderV2::new(CarHeaderV1::new(roots.clone())));
+        let mut writer = CarWriterV2::new(inner, header)?;
+        writer.rewrite_header(CarHeader::new_v1(roots.clone()))?;
+        Ok(Self {
+            writer,
+            data_offset: CarHeaderV2::new(Default::default()).data_offset,
+            roots,
+            sections: Vec::new(),
+            index: HashMap::new(),
+        })
+    }
+
+    /// resume the CARv2 file written by the blockstore, the roots must match the file.
+    /// the sections are scanned until the truncated or zero-filled tail, the new blocks
+    /// overwrite the bytes after the valid sections. the other failures e.g. the io error
+    /// and the malformed section are returned and the file is not changed.
+    /// the finalized file is turned back to unfinalized before the blocks are appended,
+    /// the old index is zeroed and the data size and the index offset in the header are cleared.
+    pub fn resume(mut inner: F, roots: Vec<Cid>) -> Result<Self, CarError> {
+        inner.rewind()?;
+        let opts = ReaderOptions::default();
+        let car_header = CarHeader::read_header_with(&mut inner, &opts)?;
+        let end = ScanEnd::of(&car_header, 0);
+        let mut header = match car_header {
+            CarHeader::V2(v2) => v2,
+            CarHeader::V1(_) => {
+                return Err(CarError::InvalidFile(
+                    "the blockstore only resume the CARv2 file".into(),
+                ))
+            }
+        };
+        if header.inner.roots != roots {
+            return Err(CarError::InvalidFile("the roots are not match".into()));
+        }
+        let data_offset = header.data_offset;
+        // the scan stops at the truncated or zero-filled tail left by the crash,
+        // the other failures are returned so the sections after them are not overwritten.
+        let scan = scan_sections(&mut inner, end, &opts)?;
+        match scan.error {
+            None | Some(CarError::UnexpectedEof { .. }) => {}
+            Some(e) => return Err(e),
+        }
+        let sections: Vec<_> = scan
+            .sections
+            .iter()
+            .map(|s| Section::new(s.cid(), s.pos() - data_offset, s.len()))
+            .collect();
+        let index = scan.index;
+        let data_size = scan.end - data_offset;
+        if header.data_size != 0 || header.has_index() {
+            // the zeros stop the scan, so the old index is not taken as the sections
+            // if the file is resumed again after a crash.
+            let file_end = inner.seek(SeekFrom::End(0))?;
+            let data_end = end.limit(file_end);
+            inner.seek(SeekFrom::Start(data_end))?;
+            io::copy(&mut io::repeat(0).take(file_end - data_end), &mut inner)?;
+            inner.flush()?;
+            header.data_size = 0;
+            header.index_offset = 0;
+            header.characteristics.set_fully_indexed(false);
+            inner.rewind()?;
+            inner.write_all(&header.encode())?;
+            inner.flush()?;
+        }
+        let writer = CarWriterV2::resume(inner, header, sections.clone(), data_size)?;
+        Ok(Self {
+            writer,
+            data_offset,
+            roots,
+            sections,
+            index,
+        })
+    }
+
+    #[inline(always)]
+    pub fn roots(&self) -> &[Cid] {
+        &self.roots
+    }
+
+    /// put the block, return false if the block is already stored or the cid is the identity cid.
+    pub fn put<T>(&mut self, cid: Cid, data: T) -> Result<bool, CarError>
+    where
+        T: AsRef<[u8]>,
+    {
+        if self.index.contains_key(&cid) || identity_data(&cid).is_some() {
+            return Ok(false);
+        }
+        let data = data.as_ref();
+        self.writer.write(cid, data)?;
+        let pos = self.writer.get_mut().stream_position()? - self.data_offset;
+        self.index.insert(cid, self.sections.len());
+        self.sections
+            .push(Section::new(cid, pos - data.len() as u64, data.len()));
+        Ok(true)
+    }
+
+    #[inline]
+    pub fn flush(&mut self) -> Result<(), CarError> {
+        self.writer.flush()
+    }
+
+    /// write the index and the CARv2 header, the blockstore is closed.
+    pub fn finalize(mut self) -> Result<(), CarError> {
+        self.writer.finalize()
+    }
+
+    fn read_data(&mut self, s: &Section) -> Result<Vec<u8>, CarError> {
+        let w = self.writer.get_mut();
+        let end = w.stream_position()?;
+        w.seek(SeekFrom::Start(self.data_offset + s.pos()))?;
+        let mut data = vec![0u8; s.len()];
+        let rs = w.read_exact(&mut data);
+        w.seek(SeekFrom::Start(end))?;
+        rs?;
+        Ok(data)
+    }
+}
+
+impl<F> Blockstore for CarBlockstoreRw<F>
+where
+    F: Read + Write + Seek,
+{
+    fn get(&mut self, cid: &Cid) -> Result<Vec<u8>, CarError> {
+        if let Some(data) = identity_data(cid) {
+            return Ok(data.to_vec());
+        }
+        let s = match self.index.get(cid) {
+            Some(idx) => self.sections[*idx].clone(),
+            None => return Err(CarError::NotFound(format!("block {cid}"))),
+        };
+        self.read_data(&s)
+    }
+
+    fn has(&self, cid: &Cid) -> Result<bool, CarError> {
+        Ok(self.index.contains_key(cid) || identity_data(cid).is_some())
+    }
+
+    fn get_size(&self, cid: &Cid) -> Result<usize, CarError> {
+        match self.index.get(cid) {
+            Some(idx) => Ok(self.sections[*idx].len()),
+            None => identity_data(cid)
+                .map(<[u8]>::len)
+                .ok_or(CarError::NotFound(format!("block {cid}"))),
+        }
+    }
+
+    fn all_keys(&self) -> Box<dyn Iterator<Item = Cid> + '_> {
+        Box::new(self.sections.iter().map(Section::cid))
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::reader::{self, CarReader};
+    use crate::utils::raw_cid;
+    use std::io::Cursor;
+
+    #[test]
+    fn test_blockstore_rw() {
+        let file = std::path::Path::new("test");
+        let file = file.join("carv1-basic.car");
+        let mut v1 = reader::new_v1(File::open(file).unwrap()).unwrap();
+        let roots = v1.header().roots();
+        let blocks: Vec<_> = v1
+            .sections()
+            .iter()
+            .map(|s| (s.cid(), v1.read_section_data(&s.cid()).unwrap()))
+            .collect();
+
+        let mut buf = Vec::new();
+        let mut store = CarBlockstoreRw::create(Cursor::new(&mut buf), roots.clone()).unwrap();
+        for (cid, data) in &blocks[..4] {
+            assert!(store.put(*cid, data).unwrap());
+            assert_eq!(&store.get(cid).unwrap(), data);
+        }
+        assert!(!store.put(blocks[0].0, &blocks[0].1).unwrap());
+        assert_eq!(store.all_keys().count(), 4);
+        store.flush().unwrap();
+        drop(store);
+
+        // the crash in the middle of the section.
+        let valid = buf.len();
+        buf.extend_from_slice(&[0x80, 0x01, 0x01]);
+        let store = CarBlockstoreRw::resume(Cursor::new(&mut buf), vec![]);
+        assert!(store.is_err());
+        let mut store = CarBlockstoreRw::resume(Cursor::new(&mut buf), roots.clone()).unwrap();
+        assert_eq!(store.all_keys().count(), 4);
+        assert_eq!(store.get(&blocks[3].0).unwrap(), blocks[3].1);
+        for (cid, data) in &blocks {
+            store.put(*cid, data).unwrap();
+        }
+        assert!(!store.has(&raw_cid(b"missing")).unwrap());
+        store.finalize().unwrap();
+        assert!(buf.len() > valid);
+
+        let mut r = reader::new_v2(Cursor::new(&buf)).unwrap();
+        assert_eq!(r.header().roots(), roots);
+        assert_eq!(r.sections().len(), blocks.len());
+        for (cid, data) in &blocks {
+            assert_eq!(&r.read_section_data(cid).unwrap(), data);
+        }
+        drop(r);
+
+        // the finalized file is resumed too.
+        let mut store = CarBlockstoreRw::resume(Cursor::new(&mut buf), roots.clone()).unwrap();
+        assert_eq!(store.all_keys().count(), blocks.len());
+        assert!(store.put(raw_cid(b"more"), b"more").unwrap());
+        store.finalize().unwrap();
+        let mut r = reader::new_v2(Cursor::new(&buf)).unwrap();
+        assert_eq!(r.read_section_data(&raw_cid(b"more")).unwrap(), b"more");
+        drop(r);
+
+        // the crash after the finalized file is resumed.
+        let mut store = CarBlockstoreRw::resume(Cursor::new(&mut buf), roots.clone()).unwrap();
+        assert!(store.put(raw_cid(b"crash"), b"crash").unwrap());
+        store.flush().unwrap();
+        drop(store);
+        let mut r = reader::new_v2(Cursor::new(&buf)).unwrap();
+        assert_eq!(r.sections().len(), blocks.len() + 2);
+        assert_eq!(r.read_section_data(&raw_cid(b"crash")).unwrap(), b"crash");
+        drop(r);
+        let mut store = CarBlockstoreRw::resume(Cursor::new(&mut buf), roots.clone()).unwrap();
+        assert_eq!(store.all_keys().count(), blocks.len() + 2);
+        assert_eq!(store.get(&raw_cid(b"more")).unwrap(), b"more");
+        assert_eq!(store.get(&raw_cid(b"crash")).unwrap(), b"crash");
+        store.finalize().unwrap();
+        let mut r = reader::new_v2(Cursor::new(&buf)).unwrap();
+        assert_eq!(r.sections().len(), blocks.len() + 2);
+        assert_eq!(r.read_section_data(&raw_cid(b"crash")).unwrap(), b"crash");
+    }
+
+    /// the reader fails after `fail_at`, e.g. the disk error.
+    struct FailingReader<'a> {
+        inner: Cursor<&'a mut Vec<u8>>,
+        fail_at: u64,
+    }
+
+    impl Read for FailingReader<'_> {
+        fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+            if self.inner.position() >= self.fail_at {
+                return Err(io::Error::new(io::ErrorKind::Other, "disk error"));
+            }
+            self.inner.read(buf)
+        }
+    }
+
+    impl Write for FailingReader<'_> {
+        fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+            self.inner.write(buf)
+        }
+
+        fn flush(&mut self) -> io::Result<()> {
+            self.inner.flush()
+        }
+    }
+
+    impl Seek for FailingReader<'_> {
+        fn seek(&mut self, pos: SeekFrom) -> io::Result<u64> {
+            self.inner.seek(pos)
+        }
+    }
+
+    #[test]
+    fn test_resume_io_error() {
+        let roots = vec![raw_cid(b"root")];
+        let mut buf = Vec::new();
+        let mut store = CarBlockstoreRw::create(Cursor::new(&mut buf), roots.clone()).unwrap();
+        for data in [&b"first"[..], b"second", b"third"] {
+            store.put(raw_cid(data), data).unwrap();
+        }
+        store.finalize().unwrap();
+
+        // the read fails in the middle of the data payload.
+        let mut r = reader::new_v2(Cursor::new(&buf)).unwrap();
+        let fail_at = r.sections()[1].section_pos() + 1;
+        drop(r);
+        let finalized = buf.clone();
+        let inner = FailingReader {
+            inner: Cursor::new(&mut buf),
+            fail_at,
+        };
+        let rs = CarBlockstoreRw::resume(inner, roots);
+        assert!(matches!(rs.err(), Some(CarError::IO(_))));
+        // the file is not changed.
+        assert_eq!(buf, finalized);
+    }
+}
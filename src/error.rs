@@ -1,3 +1,6 @@
+use std::io;
+
+use cid::Cid;
 use thiserror::Error;
 
 #[derive(Debug, Error)]
@@ -17,6 +20,127 @@ pub enum CarError {
     #[error("too large section error: {0}")]
     TooLargeSection(usize),
 
+    #[error("too many sections: more than {0}")]
+    TooManySections(usize),
+
     #[error("Not found {0}")]
     NotFound(String),
+
+    #[error("unsupported index codec: {0:#x}")]
+    UnsupportedIndexCodec(u64),
+
+    #[error("hash mismatch of {cid} at offset {offset}")]
+    HashMismatch { cid: Cid, offset: u64 },
+
+    #[error("unsupported multihash: {0:#x}")]
+    UnsupportedHash(u64),
+
+    #[error("unexpected end of file, the item at offset {offset} is truncated")]
+    UnexpectedEof { offset: u64 },
+
+    #[error("invalid varint at offset {offset}")]
+    InvalidVarint { offset: u64 },
+
+    #[error("invalid cid at offset {offset}: {reason}")]
+    InvalidCid { offset: u64, reason: String },
+
+    #[error("unsupported codec {codec:#x} of {cid}")]
+    UnsupportedCodec { cid: Cid, codec: u64 },
+
+    #[error("unsupported CAR version: {0}")]
+    UnsupportedVersion(u64),
+}
+
+impl CarError {
+    /// the byte offset of the malformed input, none for the other errors.
+    pub fn offset(&self) -> Option<u64> {
+        match *self {
+            CarError::HashMismatch { offset, .. }
+            | CarError::UnexpectedEof { offset }
+            | CarError::InvalidVarint { offset }
+            | CarError::InvalidCid { offset, .. } => Some(offset),
+            _ => None,
+        }
+    }
+
+    /// the cid of the failed block, none for the other errors.
+    pub fn cid(&self) -> Option<Cid> {
+        match *self {
+            CarError::HashMismatch { cid, .. } | CarError::UnsupportedCodec { cid, .. } => {
+                Some(cid)
+            }
+            _ => None,
+        }
+    }
+
+    /// the io error of reading the item at the offset,
+    /// the end of file is reported as the truncated item.
+    pub(crate) fn io_at(e: io::Error, offset: u64) -> Self {
+        match e.kind() {
+            io::ErrorKind::UnexpectedEof => CarError::UnexpectedEof { offset },
+            _ => CarError::IO(e),
+        }
+    }
+
+    /// the error of reading the varint at the offset.
+    pub(crate) fn varint_at(e: io::Error, offset: u64) -> Self {
+        match e.kind() {
+            io::ErrorKind::InvalidData => CarError::InvalidVarint { offset },
+            _ => CarError::io_at(e, offset),
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::{
+        header::{CarHeader, CARV2_PRAGMA},
+        reader::{self, BlockIterator, CarReader},
+        section::decode_ipld,
+    };
+    use cid::multihash::Multihash;
+    use std::io::Cursor;
+
+    #[test]
+    fn test_error_offsets() {
+        let file = std::path::Path::new("test");
+        let file = file.join("carv1-basic.car");
+        let data = std::fs::read(file).unwrap();
+        let sections = reader::new_v1(Cursor::new(&data)).unwrap().sections();
+        let last = sections.last().unwrap();
+
+        let truncated = &data[..data.len() - 1];
+        let err = reader::new_v1(Cursor::new(truncated)).err().unwrap();
+        assert!(matches!(err, CarError::UnexpectedEof { offset } if offset == last.section_pos()));
+        let err = BlockIterator::new(truncated).unwrap().last().unwrap();
+        assert_eq!(err.unwrap_err().offset(), Some(last.section_pos()));
+
+        let header_end = sections[0].section_pos() as usize;
+        let mut bad_varint = data[..header_end].to_vec();
+        bad_varint.extend_from_slice(&[0xff; 11]);
+        let err = reader::new_v1(Cursor::new(&bad_varint)).err().unwrap();
+        assert!(matches!(err, CarError::InvalidVarint { offset } if offset == header_end as u64));
+
+        let mut bad_cid = data[..header_end].to_vec();
+        bad_cid.extend_from_slice(&[0x04, 0x05, 0x00, 0x00, 0x00]);
+        let err = reader::new_v1(Cursor::new(&bad_cid)).err().unwrap();
+        assert!(
+            matches!(err, CarError::InvalidCid { offset, .. } if offset == header_end as u64 + 1)
+        );
+
+        let mut v3 = CARV2_PRAGMA;
+        v3[CARV2_PRAGMA.len() - 1] = 0x03;
+        let err = CarHeader::read_header(Cursor::new(&v3)).unwrap_err();
+        assert!(matches!(err, CarError::UnsupportedVersion(3)));
+
+        let cid = Cid::new_v1(0x99, Multihash::wrap(0x00, b"data").unwrap());
+        let err = decode_ipld(cid, b"data".to_vec()).unwrap_err();
+        assert!(matches!(
+            err,
+            CarError::UnsupportedCodec { codec: 0x99, .. }
+        ));
+        assert_eq!(err.cid(), Some(cid));
+        assert_eq!(err.offset(), None);
+    }
 }
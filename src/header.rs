@@ -1,18 +1,44 @@
 mod header_v1;
-use std::io;
+mod header_v2;
+use std::io::{self, SeekFrom};
 
-pub(crate) use header_v1::CarHeaderV1;
+pub use header_v1::CarHeaderV1;
+pub use header_v2::{
+    CarHeaderV2, Characteristics, CARV2_HEADER_SIZE, CARV2_PRAGMA, CARV2_PRAGMA_SIZE,
+};
 
 use cid::Cid;
 use ipld::prelude::Codec;
 use ipld_cbor::DagCborCodec;
 
-use crate::{error::CarError, reader::read_block};
+use crate::{
+    error::CarError,
+    reader::{read_block_at, ReaderOptions},
+    Ipld,
+};
 
 #[derive(Clone, Debug)]
 pub enum CarHeader {
     V1(CarHeaderV1),
-    V2(),
+    V2(CarHeaderV2),
+}
+
+/// the header summary of the CAR file, which is read without scanning the sections.
+#[derive(Clone, Debug)]
+pub struct HeaderInfo {
+    pub header: CarHeader,
+    pub version: u64,
+    pub roots: Vec<Cid>,
+    /// the position of the first section, relative to the start of the file.
+    pub data_start: u64,
+    /// the CARv2 characteristics, none for CARv1.
+    pub characteristics: Option<Characteristics>,
+    /// the CARv2 data payload offset, none for CARv1.
+    pub data_offset: Option<u64>,
+    /// the CARv2 data payload size, none for CARv1.
+    pub data_size: Option<u64>,
+    /// the CARv2 index offset, none for CARv1 or the CARv2 file without index.
+    pub index_offset: Option<u64>,
 }
 
 impl CarHeader {
@@ -20,41 +46,182 @@ impl CarHeader {
         CarHeader::V1(CarHeaderV1::new(roots))
     }
 
+    pub fn new_v2(roots: Vec<Cid>) -> Self {
+        CarHeader::V2(CarHeaderV2::new(CarHeaderV1::new(roots)))
+    }
+
+    #[inline]
+    pub fn version(&self) -> u64 {
+        match *self {
+            CarHeader::V1(_) => 1,
+            CarHeader::V2(_) => 2,
+        }
+    }
+
     pub fn roots(&self) -> Vec<Cid> {
         match *self {
             CarHeader::V1(ref v1) => v1.roots.clone(),
-            CarHeader::V2() => todo!(),
+            CarHeader::V2(ref v2) => v2.inner.roots.clone(),
         }
     }
 
+    /// read the header from the reader with the default options.
+    /// for the CARv2 file, the reader will be positioned after the inner CARv1 header.
+    #[inline]
     pub fn read_header<R>(r: R) -> Result<CarHeader, CarError>
     where
         R: io::Read + io::Seek,
     {
-        let data = match read_block(r) {
+        CarHeader::read_header_with(r, &ReaderOptions::default())
+    }
+
+    /// read the header from the reader, the header is checked by the options.
+    /// for the CARv2 file, the reader will be positioned after the inner CARv1 header.
+    pub fn read_header_with<R>(mut r: R, opts: &ReaderOptions) -> Result<CarHeader, CarError>
+    where
+        R: io::Read + io::Seek,
+    {
+        let start = r.stream_position()?;
+        let data = match read_block_at(&mut r, opts, start) {
+            Ok(Some(d)) => d,
+            Ok(None) => return Err(CarError::Parsing("Invalid Header".into())),
+            Err(e) => return Err(e),
+        };
+        if let Some(header) = CarHeader::decode_first_block(&data, opts)? {
+            return Ok(header);
+        }
+        let mut buf = [0u8; CARV2_HEADER_SIZE];
+        r.read_exact(&mut buf)
+            .map_err(|e| CarError::io_at(e, start + CARV2_PRAGMA_SIZE as u64))?;
+        let v2 = CarHeaderV2::decode(&buf, Default::default())?;
+        r.seek(SeekFrom::Start(start + v2.data_offset))?;
+        let data = match read_block_at(&mut r, opts, start + v2.data_offset) {
+            Ok(Some(d)) => d,
+            Ok(None) => return Err(CarError::Parsing("Invalid inner Header".into())),
+            Err(e) => return Err(e),
+        };
+        let inner = match CarHeader::decode_with(&data[..], opts)? {
+            CarHeader::V1(v1) => v1,
+            CarHeader::V2(_) => unreachable!("decode only support CARv1 header"),
+        };
+        Ok(CarHeader::V2(CarHeaderV2 { inner, ..v2 }))
+    }
+
+    /// read the header from the non-seekable reader, the header is checked by the options.
+    /// for the CARv2 file, the padding before the data payload is skipped by reading,
+    /// the reader will be positioned after the inner CARv1 header, the index codec is not read.
+    pub fn read_stream_header_with<R>(mut r: R, opts: &ReaderOptions) -> Result<CarHeader, CarError>
+    where
+        R: io::Read,
+    {
+        let data = match read_block_at(&mut r, opts, 0) {
             Ok(Some(d)) => d,
             Ok(None) => return Err(CarError::Parsing("Invalid Header".into())),
             Err(e) => return Err(e),
         };
-        let header = CarHeader::decode(&data[..])?;
-        Ok(header)
+        if let Some(header) = CarHeader::decode_first_block(&data, opts)? {
+            return Ok(header);
+        }
+        let mut buf = [0u8; CARV2_HEADER_SIZE];
+        r.read_exact(&mut buf)
+            .map_err(|e| CarError::io_at(e, CARV2_PRAGMA_SIZE as u64))?;
+        let v2 = CarHeaderV2::decode(&buf, Default::default())?;
+        let padding = v2
+            .data_offset
+            .checked_sub((CARV2_PRAGMA_SIZE + CARV2_HEADER_SIZE) as u64)
+            .ok_or(CarError::InvalidFile(
+                "the data offset overlaps the header".into(),
+            ))?;
+        if io::copy(&mut io::Read::take(&mut r, padding), &mut io::sink())? != padding {
+            return Err(CarError::UnexpectedEof {
+                offset: (CARV2_PRAGMA_SIZE + CARV2_HEADER_SIZE) as u64,
+            });
+        }
+        let data = match read_block_at(&mut r, opts, v2.data_offset) {
+            Ok(Some(d)) => d,
+            Ok(None) => return Err(CarError::Parsing("Invalid inner Header".into())),
+            Err(e) => return Err(e),
+        };
+        let inner = match CarHeader::decode_with(&data[..], opts)? {
+            CarHeader::V1(v1) => v1,
+            CarHeader::V2(_) => unreachable!("decode only support CARv1 header"),
+        };
+        Ok(CarHeader::V2(CarHeaderV2 { inner, ..v2 }))
+    }
+
+    /// read the header summary, only the header is read, the sections are not scanned.
+    pub fn inspect<R>(mut r: R) -> Result<HeaderInfo, CarError>
+    where
+        R: io::Read + io::Seek,
+    {
+        let start = r.stream_position()?;
+        let header = CarHeader::read_header(&mut r)?;
+        let data_start = r.stream_position()? - start;
+        let v2 = match header {
+            CarHeader::V2(ref v2) => Some(v2),
+            CarHeader::V1(_) => None,
+        };
+        Ok(HeaderInfo {
+            version: header.version(),
+            roots: header.roots(),
+            data_start,
+            characteristics: v2.map(|v2| v2.characteristics),
+            data_offset: v2.map(|v2| v2.data_offset),
+            data_size: v2.map(|v2| v2.data_size),
+            index_offset: v2.filter(|v2| v2.has_index()).map(|v2| v2.index_offset),
+            header,
+        })
     }
 
+    /// decode the CARv1 header with the default options,
+    /// the CARv2 header should be read by `read_header`.
+    #[inline]
     pub fn decode(buf: &[u8]) -> Result<CarHeader, CarError> {
-        let header: CarHeaderV1 = DagCborCodec
-            .decode(buf)
-            .map_err(|e| CarError::Parsing(e.to_string()))?;
-        if header.roots.is_empty() {
+        CarHeader::decode_with(buf, &ReaderOptions::default())
+    }
+
+    /// decode the CARv1 header, the header is checked by the options.
+    /// the CARv2 header should be read by `read_header_with`.
+    pub fn decode_with(buf: &[u8], opts: &ReaderOptions) -> Result<CarHeader, CarError> {
+        CarHeader::from_ipld(decode_ipld(buf)?, opts)
+    }
+
+    /// decode the first block of the file, it's the CARv1 header or the CARv2 pragma.
+    /// return none for the CARv2 pragma.
+    pub(crate) fn decode_first_block(
+        buf: &[u8],
+        opts: &ReaderOptions,
+    ) -> Result<Option<CarHeader>, CarError> {
+        let header = decode_ipld(buf)?;
+        if ipld_version(&header)? == 2 {
+            return Ok(None);
+        }
+        CarHeader::from_ipld(header, opts).map(Some)
+    }
+
+    fn from_ipld(header: Ipld, opts: &ReaderOptions) -> Result<CarHeader, CarError> {
+        let version = ipld_version(&header)?;
+        if version != 1 {
+            return Err(CarError::InvalidFile(format!(
+                "the CAR version {version} header can't be decoded from header block"
+            )));
+        }
+        let header = CarHeaderV1::from_ipld(header)?;
+        if header.roots.is_empty() && !opts.is_empty_roots_allowed() {
             return Err(CarError::Parsing("car roots is empty".to_owned()));
         }
-        if header.version != 1 {
-            return Err(CarError::InvalidFile(
-                "Now CAR version 1 is supported only".to_string(),
-            ));
+        if !header.extra.is_empty() && !opts.is_unknown_header_fields_allowed() {
+            let keys: Vec<&str> = header.extra.keys().map(String::as_str).collect();
+            return Err(CarError::Parsing(format!(
+                "unknown car header fields: {}",
+                keys.join(", ")
+            )));
         }
         Ok(CarHeader::V1(header))
     }
 
+    /// encode the header.
+    /// for the CARv2 is the pragma and the fixed header, the inner CARv1 header is not included.
     pub fn encode(&self) -> Result<Vec<u8>, CarError> {
         match *self {
             CarHeader::V1(ref v1) => {
@@ -63,7 +230,101 @@ impl CarHeader {
                     .map_err(|e| CarError::Parsing(e.to_string()))?;
                 Ok(data)
             }
-            CarHeader::V2() => todo!(),
+            CarHeader::V2(ref v2) => Ok(v2.encode()),
+        }
+    }
+}
+
+fn decode_ipld(buf: &[u8]) -> Result<Ipld, CarError> {
+    DagCborCodec
+        .decode(buf)
+        .map_err(|e| CarError::Parsing(e.to_string()))
+}
+
+/// get the version from the decoded header block.
+fn ipld_version(header: &Ipld) -> Result<u64, CarError> {
+    match header.get("version") {
+        Ok(Ipld::Integer(v @ (1 | 2))) => Ok(*v as u64),
+        Ok(Ipld::Integer(v)) => Err(CarError::UnsupportedVersion(*v as u64)),
+        _ => Err(CarError::Parsing("car version is missing".into())),
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::index::IndexCodec;
+    use crate::utils::wrap_v1;
+    use std::io::Cursor;
+
+    #[test]
+    fn test_inspect() {
+        let file = std::path::Path::new("test");
+        let file = file.join("carv1-basic.car");
+        let v1 = std::fs::read(file).unwrap();
+        let info = CarHeader::inspect(Cursor::new(&v1)).unwrap();
+        assert_eq!(info.version, 1);
+        assert_eq!(info.roots.len(), 1);
+        assert!(info.characteristics.is_none());
+        let header_len = info.header.encode().unwrap().len();
+        assert_eq!(info.data_start, header_len as u64 + 1);
+
+        let mut v2 = Vec::new();
+        wrap_v1(Cursor::new(&v1), &mut v2, IndexCodec::MultihashIndexSorted).unwrap();
+        let v2_info = CarHeader::inspect(Cursor::new(&v2)).unwrap();
+        assert_eq!(v2_info.version, 2);
+        assert_eq!(v2_info.roots, info.roots);
+        assert_eq!(v2_info.data_offset, Some(51));
+        assert_eq!(v2_info.data_size, Some(v1.len() as u64));
+        assert_eq!(v2_info.index_offset, Some(51 + v1.len() as u64));
+        assert_eq!(v2_info.data_start, 51 + info.data_start);
+        assert!(v2_info.characteristics.unwrap().is_fully_indexed());
+    }
+
+    #[test]
+    fn test_header_ignores_index() {
+        use crate::reader::{self, CarReader};
+        let file = std::path::Path::new("test");
+        let file = file.join("carv1-basic.car");
+        let v1 = std::fs::read(file).unwrap();
+        let mut v2 = Vec::new();
+        wrap_v1(Cursor::new(&v1), &mut v2, IndexCodec::MultihashIndexSorted).unwrap();
+        let index_offset = 51 + v1.len();
+
+        // the unknown index codec 0x0403.
+        let mut unknown = v2.clone();
+        unknown[index_offset..index_offset + 2].copy_from_slice(&[0x83, 0x08]);
+        assert!(CarHeader::inspect(Cursor::new(&unknown)).is_ok());
+        let lazy = reader::new_lazy(Cursor::new(&unknown)).unwrap();
+        assert_eq!(lazy.sections().len(), 6);
+        assert!(matches!(
+            reader::new_v2(Cursor::new(&unknown)).err(),
+            Some(CarError::UnsupportedIndexCodec(0x0403))
+        ));
+
+        // the index is truncated.
+        let truncated = &v2[..index_offset];
+        assert!(CarHeader::inspect(Cursor::new(truncated)).is_ok());
+        assert!(reader::new_lazy(Cursor::new(truncated)).is_ok());
+        assert!(reader::new_v2(Cursor::new(truncated)).is_err());
+    }
+
+    #[test]
+    fn test_header_strictness() {
+        let mut header = CarHeaderV1::new(Vec::new());
+        header
+            .extra
+            .insert("ext".into(), Ipld::String("value".into()));
+        let buf = CarHeader::V1(header.clone()).encode().unwrap();
+        assert!(CarHeader::decode_with(&buf, &ReaderOptions::strict()).is_err());
+        assert!(CarHeader::decode(&buf).is_err());
+        match CarHeader::decode_with(&buf, &ReaderOptions::lenient()).unwrap() {
+            CarHeader::V1(v1) => assert_eq!(v1, header),
+            CarHeader::V2(_) => panic!("should be the CARv1 header"),
         }
+        let lenient = CarHeader::decode_with(&buf, &ReaderOptions::lenient()).unwrap();
+        assert_eq!(lenient.encode().unwrap(), buf);
+        let strict_roots = ReaderOptions::strict().allow_empty_roots(true);
+        assert!(CarHeader::decode_with(&buf, &strict_roots).is_err());
     }
 }
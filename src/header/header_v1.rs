@@ -1,16 +1,68 @@
+use std::collections::BTreeMap;
+use std::io::{Read, Seek, Write};
+
 use cid::Cid;
+use ipld::codec::{Decode, Encode};
+use ipld_cbor::DagCborCodec;
+
+use crate::{error::CarError, Ipld};
 
-#[derive(Debug, Clone, PartialEq, Eq, Default, ipld::DagCbor)]
+#[derive(Debug, Clone, PartialEq)]
 pub struct CarHeaderV1 {
-    #[ipld]
     pub roots: Vec<Cid>,
-    #[ipld]
     pub version: u64,
+    /// the unknown fields of the header, they are kept for the encode round-trips.
+    pub extra: BTreeMap<String, Ipld>,
 }
 
 impl CarHeaderV1 {
     pub fn new(roots: Vec<Cid>) -> Self {
-        Self { roots, version: 1 }
+        Self {
+            roots,
+            version: 1,
+            extra: BTreeMap::new(),
+        }
+    }
+
+    /// convert the header from the decoded ipld map, the unknown fields are kept in `extra`.
+    pub(crate) fn from_ipld(ipld: Ipld) -> Result<Self, CarError> {
+        let mut map = match ipld {
+            Ipld::Map(m) => m,
+            _ => return Err(CarError::Parsing("car header is not a map".into())),
+        };
+        let roots = match map.remove("roots") {
+            Some(Ipld::List(roots)) => roots
+                .into_iter()
+                .map(|r| match r {
+                    Ipld::Link(cid) => Ok(cid),
+                    _ => Err(CarError::Parsing("car root is not a cid".into())),
+                })
+                .collect::<Result<Vec<_>, _>>()?,
+            _ => return Err(CarError::Parsing("car roots is missing".into())),
+        };
+        let version = match map.remove("version") {
+            Some(Ipld::Integer(v)) if v >= 0 => v as u64,
+            _ => return Err(CarError::Parsing("car version is missing".into())),
+        };
+        Ok(Self {
+            roots,
+            version,
+            extra: map,
+        })
+    }
+
+    pub(crate) fn to_ipld(&self) -> Ipld {
+        let mut map = self.extra.clone();
+        let roots = self.roots.iter().map(|c| Ipld::Link(*c)).collect();
+        map.insert("roots".into(), Ipld::List(roots));
+        map.insert("version".into(), Ipld::Integer(self.version as i128));
+        Ipld::Map(map)
+    }
+}
+
+impl Default for CarHeaderV1 {
+    fn default() -> Self {
+        Self::new(Vec::new())
     }
 }
 
@@ -20,6 +72,19 @@ impl From<Vec<Cid>> for CarHeaderV1 {
     }
 }
 
+impl Encode<DagCborCodec> for CarHeaderV1 {
+    fn encode<W: Write>(&self, c: DagCborCodec, w: &mut W) -> ipld::Result<()> {
+        self.to_ipld().encode(c, w)
+    }
+}
+
+impl Decode<DagCborCodec> for CarHeaderV1 {
+    fn decode<R: Read + Seek>(c: DagCborCodec, r: &mut R) -> ipld::Result<Self> {
+        let ipld = Ipld::decode(c, r)?;
+        Ok(Self::from_ipld(ipld)?)
+    }
+}
+
 #[cfg(test)]
 mod test {
     use super::*;
@@ -0,0 +1,149 @@
+use super::CarHeaderV1;
+use crate::{error::CarError, index::IndexCodec};
+
+/// the CARv2 pragma, the varint length prefix followed by the dag-cbor `{"version": 2}`.
+pub const CARV2_PRAGMA: [u8; 11] = [
+    0x0a, 0xa1, 0x67, 0x76, 0x65, 0x72, 0x73, 0x69, 0x6f, 0x6e, 0x02,
+];
+
+pub const CARV2_PRAGMA_SIZE: usize = CARV2_PRAGMA.len();
+
+/// the fixed size of the CARv2 header: characteristics, data offset, data size, index offset.
+pub const CARV2_HEADER_SIZE: usize = 40;
+
+/// the characteristics bitfield of the CARv2 header.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct Characteristics {
+    pub hi: u64,
+    pub lo: u64,
+}
+
+impl Characteristics {
+    const FULLY_INDEXED: u64 = 1 << 7;
+
+    pub fn new(hi: u64, lo: u64) -> Self {
+        Self { hi, lo }
+    }
+
+    /// the index contains all the blocks in the data payload, include the identity cid and duplicate blocks.
+    #[inline(always)]
+    pub fn is_fully_indexed(&self) -> bool {
+        self.hi & Self::FULLY_INDEXED != 0
+    }
+
+    #[inline]
+    pub fn set_fully_indexed(&mut self, fully_indexed: bool) {
+        if fully_indexed {
+            self.hi |= Self::FULLY_INDEXED;
+        } else {
+            self.hi &= !Self::FULLY_INDEXED;
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Default)]
+pub struct CarHeaderV2 {
+    pub characteristics: Characteristics,
+    pub data_offset: u64,
+    pub data_size: u64,
+    pub index_offset: u64,
+    /// the codec of the index, it's set when the index is read by the CARv2 reader,
+    /// and used to generate the index when the file is written.
+    pub index_codec: Option<IndexCodec>,
+    /// the header of the inner CARv1 payload.
+    pub inner: CarHeaderV1,
+}
+
+impl CarHeaderV2 {
+    pub fn new(inner: CarHeaderV1) -> Self {
+        Self {
+            inner,
+            data_offset: (CARV2_PRAGMA_SIZE + CARV2_HEADER_SIZE) as u64,
+            ..Default::default()
+        }
+    }
+
+    /// decode the fixed 40 bytes header which follows the pragma.
+    /// `inner` is the header of the inner CARv1 payload.
+    pub fn decode(buf: &[u8], inner: CarHeaderV1) -> Result<Self, CarError> {
+        if buf.len() < CARV2_HEADER_SIZE {
+            return Err(CarError::Parsing("CARv2 header is too short".into()));
+        }
+        let u64_at = |i: usize| {
+            let mut bs = [0u8; 8];
+            bs.copy_from_slice(&buf[i..i + 8]);
+            u64::from_le_bytes(bs)
+        };
+        Ok(Self {
+            characteristics: Characteristics::new(u64_at(0), u64_at(8)),
+            data_offset: u64_at(16),
+            data_size: u64_at(24),
+            index_offset: u64_at(32),
+            index_codec: None,
+            inner,
+        })
+    }
+
+    /// encode the pragma and the fixed 40 bytes header.
+    pub fn encode(&self) -> Vec<u8> {
+        let mut buf = Vec::with_capacity(CARV2_PRAGMA_SIZE + CARV2_HEADER_SIZE);
+        buf.extend_from_slice(&CARV2_PRAGMA);
+        buf.extend_from_slice(&self.characteristics.hi.to_le_bytes());
+        buf.extend_from_slice(&self.characteristics.lo.to_le_bytes());
+        buf.extend_from_slice(&self.data_offset.to_le_bytes());
+        buf.extend_from_slice(&self.data_size.to_le_bytes());
+        buf.extend_from_slice(&self.index_offset.to_le_bytes());
+        buf
+    }
+
+    #[inline(always)]
+    pub fn has_index(&self) -> bool {
+        self.index_offset != 0
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::header::CarHeader;
+    use cid::multihash::{Code::Blake2b256, MultihashDigest};
+    use cid::Cid;
+    use integer_encoding::VarIntWriter;
+    use ipld_cbor::DagCborCodec;
+    use std::io::Write;
+
+    #[test]
+    fn test_head_v2() {
+        let digest = Blake2b256.digest(b"test");
+        let cid = Cid::new_v1(DagCborCodec.into(), digest);
+        let mut header = CarHeaderV2::new(CarHeaderV1::new(vec![cid]));
+        header.characteristics.set_fully_indexed(true);
+        header.data_offset += 5;
+        header.index_codec = Some(IndexCodec::IndexSorted);
+        let inner = CarHeader::V1(header.inner.clone()).encode().unwrap();
+        header.data_size = (inner.len() + 1) as u64;
+        header.index_offset = header.data_offset + header.data_size;
+        let mut bytes = header.encode();
+        bytes.extend_from_slice(&[0u8; 5]);
+        bytes.write_varint(inner.len()).unwrap();
+        bytes.write_all(&inner).unwrap();
+        bytes.write_varint(IndexCodec::IndexSorted.code()).unwrap();
+        let mut cursor = std::io::Cursor::new(&bytes);
+        match CarHeader::read_header(&mut cursor).unwrap() {
+            CarHeader::V2(v2) => {
+                assert!(v2.characteristics.is_fully_indexed());
+                // the index is not read with the header.
+                assert_eq!(v2.index_codec, None);
+                assert_eq!(
+                    v2,
+                    CarHeaderV2 {
+                        index_codec: None,
+                        ..header.clone()
+                    }
+                );
+            }
+            CarHeader::V1(_) => panic!("should be the CARv2 header"),
+        }
+        assert_eq!(cursor.position(), header.index_offset);
+    }
+}
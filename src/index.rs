@@ -0,0 +1,182 @@
+mod index_sorted;
+mod mh_index_sorted;
+mod multi_width;
+use std::io;
+
+pub use index_sorted::IndexSorted;
+pub use mh_index_sorted::MultihashIndexSorted;
+
+use cid::Cid;
+use integer_encoding::{VarIntReader, VarIntWriter};
+
+use crate::{
+    error::CarError,
+    header::CarHeader,
+    reader::{scan_sections, ReaderOptions, ScanEnd},
+    section::Section,
+};
+
+/// the multicodec code of the `IndexSorted` index.
+pub const INDEX_SORTED_CODEC: u64 = 0x0400;
+
+/// the multicodec code of the `MultihashIndexSorted` index.
+pub const MULTIHASH_INDEX_SORTED_CODEC: u64 = 0x0401;
+
+/// the codec of the CARv2 index.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum IndexCodec {
+    IndexSorted,
+    #[default]
+    MultihashIndexSorted,
+}
+
+impl IndexCodec {
+    #[inline(always)]
+    pub fn code(&self) -> u64 {
+        match self {
+            IndexCodec::IndexSorted => INDEX_SORTED_CODEC,
+            IndexCodec::MultihashIndexSorted => MULTIHASH_INDEX_SORTED_CODEC,
+        }
+    }
+}
+
+impl TryFrom<u64> for IndexCodec {
+    type Error = CarError;
+
+    fn try_from(code: u64) -> Result<Self, Self::Error> {
+        match code {
+            INDEX_SORTED_CODEC => Ok(IndexCodec::IndexSorted),
+            MULTIHASH_INDEX_SORTED_CODEC => Ok(IndexCodec::MultihashIndexSorted),
+            code => Err(CarError::UnsupportedIndexCodec(code)),
+        }
+    }
+}
+
+/// the CARv2 index.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Index {
+    Sorted(IndexSorted),
+    MultihashSorted(MultihashIndexSorted),
+}
+
+impl Index {
+    /// build the index with the codec from the sections.
+    /// `data_offset` is the position of the data payload, it's 0 for the CARv1 file.
+    pub fn from_sections<'a>(
+        codec: IndexCodec,
+        sections: impl IntoIterator<Item = &'a Section>,
+        data_offset: u64,
+    ) -> Self {
+        match codec {
+            IndexCodec::IndexSorted => {
+                Index::Sorted(IndexSorted::from_sections(sections, data_offset))
+            }
+            IndexCodec::MultihashIndexSorted => {
+                Index::MultihashSorted(MultihashIndexSorted::from_sections(sections, data_offset))
+            }
+        }
+    }
+
+    #[inline(always)]
+    pub fn codec(&self) -> IndexCodec {
+        match self {
+            Index::Sorted(_) => IndexCodec::IndexSorted,
+            Index::MultihashSorted(_) => IndexCodec::MultihashIndexSorted,
+        }
+    }
+
+    /// get the section offset of the cid.
+    #[inline]
+    pub fn get(&self, cid: &Cid) -> Option<u64> {
+        match self {
+            Index::Sorted(idx) => idx.get(cid),
+            Index::MultihashSorted(idx) => idx.get(cid),
+        }
+    }
+
+    /// all the section offsets in the index.
+    pub fn offsets(&self) -> Box<dyn Iterator<Item = u64> + '_> {
+        match self {
+            Index::Sorted(idx) => Box::new(idx.offsets()),
+            Index::MultihashSorted(idx) => Box::new(idx.offsets()),
+        }
+    }
+
+    #[inline]
+    pub fn len(&self) -> usize {
+        match self {
+            Index::Sorted(idx) => idx.len(),
+            Index::MultihashSorted(idx) => idx.len(),
+        }
+    }
+
+    #[inline]
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+}
+
+/// scan the sections of the CARv1 file and generate the index.
+/// the offsets are relative to the current position of the reader.
+pub fn generate_index<R>(mut r: R, codec: IndexCodec) -> Result<Index, CarError>
+where
+    R: io::Read + io::Seek,
+{
+    let start = r.stream_position()?;
+    let header = CarHeader::read_header(&mut r)?;
+    if let CarHeader::V2(_) = header {
+        return Err(CarError::InvalidFile(
+            "the index should be generated from the CARv1 file".into(),
+        ));
+    }
+    let scan = scan_sections(&mut r, ScanEnd::Eof, &ReaderOptions::default())?.into_result()?;
+    Ok(Index::from_sections(codec, scan.sections.iter(), start))
+}
+
+/// read the index codec, the multicodec code prefix of the index.
+pub fn read_index_codec<R>(mut r: R) -> Result<IndexCodec, CarError>
+where
+    R: io::Read,
+{
+    let code: u64 = r.read_varint()?;
+    code.try_into()
+}
+
+/// read the index, the index is the varint multicodec code followed by the index payload.
+pub fn read_index<R>(mut r: R) -> Result<Index, CarError>
+where
+    R: io::Read,
+{
+    match read_index_codec(&mut r)? {
+        IndexCodec::IndexSorted => IndexSorted::decode(r).map(Index::Sorted),
+        IndexCodec::MultihashIndexSorted => {
+            MultihashIndexSorted::decode(r).map(Index::MultihashSorted)
+        }
+    }
+}
+
+/// write the index with the multicodec code prefix.
+pub fn write_index<W>(mut w: W, index: &Index) -> Result<(), CarError>
+where
+    W: io::Write,
+{
+    w.write_varint(index.codec().code())?;
+    match index {
+        Index::Sorted(idx) => idx.encode(w),
+        Index::MultihashSorted(idx) => idx.encode(w),
+    }
+}
+
+#[inline]
+pub(crate) fn read_u32_le<R: io::Read>(mut r: R) -> Result<u32, CarError> {
+    let mut bs = [0u8; 4];
+    r.read_exact(&mut bs)?;
+    Ok(u32::from_le_bytes(bs))
+}
+
+#[inline]
+pub(crate) fn read_u64_le<R: io::Read>(mut r: R) -> Result<u64, CarError> {
+    let mut bs = [0u8; 8];
+    r.read_exact(&mut bs)?;
+    Ok(u64::from_le_bytes(bs))
+}
@@ -0,0 +1,108 @@
+use std::io;
+
+use cid::Cid;
+
+use super::multi_width::MultiWidthIndex;
+use crate::{error::CarError, section::Section};
+
+/// the legacy `IndexSorted` CARv2 index, the buckets are grouped by the digest width,
+/// every bucket contains sorted (digest, offset) records, the multihash code is not recorded.
+/// the offset is the position of the section relative to the start of the data payload.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct IndexSorted(MultiWidthIndex);
+
+impl IndexSorted {
+    /// build the index from the sections.
+    /// `data_offset` is the position of the data payload, it's 0 for the CARv1 file.
+    pub fn from_sections<'a>(
+        sections: impl IntoIterator<Item = &'a Section>,
+        data_offset: u64,
+    ) -> Self {
+        let hashes: Vec<_> = sections
+            .into_iter()
+            .map(|s| (*s.cid().hash(), s.section_pos() - data_offset))
+            .collect();
+        Self::from_records(hashes.iter().map(|(h, offset)| (h.digest(), *offset)))
+    }
+
+    /// build the index from the (digest, offset) records.
+    pub fn from_records<'a>(records: impl IntoIterator<Item = (&'a [u8], u64)>) -> Self {
+        Self(MultiWidthIndex::new(records))
+    }
+
+    /// get the section offset of the cid, only the digest of the cid is compared.
+    #[inline]
+    pub fn get(&self, cid: &Cid) -> Option<u64> {
+        self.0.get(cid.hash().digest())
+    }
+
+    /// iterate all the (digest, offset) records, ordered by the digest width and the digest.
+    pub fn iter(&self) -> impl Iterator<Item = (&[u8], u64)> + '_ {
+        self.0.iter()
+    }
+
+    /// all the section offsets in the index.
+    pub fn offsets(&self) -> impl Iterator<Item = u64> + '_ {
+        self.iter().map(|(_, offset)| offset)
+    }
+
+    #[inline]
+    pub fn len(&self) -> usize {
+        self.0.len()
+    }
+
+    #[inline]
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// decode the index payload, the multicodec code prefix is not included.
+    #[inline]
+    pub fn decode<R>(r: R) -> Result<Self, CarError>
+    where
+        R: io::Read,
+    {
+        MultiWidthIndex::decode(r).map(Self)
+    }
+
+    /// encode the index payload, the multicodec code prefix is not included.
+    #[inline]
+    pub fn encode<W>(&self, w: W) -> Result<(), CarError>
+    where
+        W: io::Write,
+    {
+        self.0.encode(w)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::index::{read_index, write_index, Index};
+    use crate::utils::raw_cid;
+
+    #[test]
+    fn test_index_sorted() {
+        let cids: Vec<_> = (0..10u8).map(|i| raw_cid(&[i])).collect();
+        let hashes: Vec<_> = cids.iter().map(|c| *c.hash()).collect();
+        let index = IndexSorted::from_records(
+            hashes
+                .iter()
+                .enumerate()
+                .map(|(i, h)| (h.digest(), i as u64 * 100)),
+        );
+        let index = Index::Sorted(index);
+        let mut buf = Vec::new();
+        write_index(&mut buf, &index).unwrap();
+        let decoded = read_index(&buf[..]).unwrap();
+        assert_eq!(decoded, index);
+        for (i, cid) in cids.iter().enumerate() {
+            assert_eq!(decoded.get(cid), Some(i as u64 * 100));
+        }
+        buf[..2].copy_from_slice(&[0x82, 0x08]);
+        assert!(matches!(
+            read_index(&buf[..]),
+            Err(CarError::UnsupportedIndexCodec(0x0402))
+        ));
+    }
+}
@@ -0,0 +1,152 @@
+use std::{collections::BTreeMap, io};
+
+use cid::Cid;
+
+use super::{multi_width::MultiWidthIndex, read_u32_le, read_u64_le};
+use crate::{error::CarError, section::Section};
+
+/// the `MultihashIndexSorted` CARv2 index, the buckets are grouped by the multihash code,
+/// then by the digest width, every bucket contains sorted (digest, offset) records.
+/// the offset is the position of the section relative to the start of the data payload.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct MultihashIndexSorted(BTreeMap<u64, MultiWidthIndex>);
+
+impl MultihashIndexSorted {
+    /// build the index from the sections.
+    /// `data_offset` is the position of the data payload, it's 0 for the CARv1 file.
+    pub fn from_sections<'a>(
+        sections: impl IntoIterator<Item = &'a Section>,
+        data_offset: u64,
+    ) -> Self {
+        let hashes: Vec<_> = sections
+            .into_iter()
+            .map(|s| (*s.cid().hash(), s.section_pos() - data_offset))
+            .collect();
+        Self::from_records(
+            hashes
+                .iter()
+                .map(|(h, offset)| (h.code(), h.digest(), *offset)),
+        )
+    }
+
+    /// build the index from the (multihash code, digest, offset) records.
+    pub fn from_records<'a>(records: impl IntoIterator<Item = (u64, &'a [u8], u64)>) -> Self {
+        let mut groups: BTreeMap<u64, Vec<(&[u8], u64)>> = BTreeMap::new();
+        for (code, digest, offset) in records {
+            groups.entry(code).or_default().push((digest, offset));
+        }
+        let buckets = groups
+            .into_iter()
+            .map(|(code, recs)| (code, MultiWidthIndex::new(recs)))
+            .collect();
+        Self(buckets)
+    }
+
+    /// get the section offset of the cid.
+    pub fn get(&self, cid: &Cid) -> Option<u64> {
+        let hash = cid.hash();
+        self.0
+            .get(&hash.code())
+            .and_then(|idx| idx.get(hash.digest()))
+    }
+
+    /// iterate all the (multihash code, digest, offset) records,
+    /// ordered by the multihash code, the digest width and the digest.
+    pub fn iter(&self) -> impl Iterator<Item = (u64, &[u8], u64)> + '_ {
+        self.0.iter().flat_map(|(code, idx)| {
+            idx.iter()
+                .map(move |(digest, offset)| (*code, digest, offset))
+        })
+    }
+
+    /// all the section offsets in the index.
+    pub fn offsets(&self) -> impl Iterator<Item = u64> + '_ {
+        self.iter().map(|(_, _, offset)| offset)
+    }
+
+    #[inline]
+    pub fn len(&self) -> usize {
+        self.0.values().map(MultiWidthIndex::len).sum()
+    }
+
+    #[inline]
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// decode the index payload, the multicodec code prefix is not included.
+    pub fn decode<R>(mut r: R) -> Result<Self, CarError>
+    where
+        R: io::Read,
+    {
+        let count = read_u32_le(&mut r)? as i32;
+        if count < 0 {
+            return Err(CarError::Parsing("invalid index code count".into()));
+        }
+        let mut buckets = BTreeMap::new();
+        for _ in 0..count {
+            let code = read_u64_le(&mut r)?;
+            buckets.insert(code, MultiWidthIndex::decode(&mut r)?);
+        }
+        Ok(Self(buckets))
+    }
+
+    /// encode the index payload, the multicodec code prefix is not included.
+    pub fn encode<W>(&self, mut w: W) -> Result<(), CarError>
+    where
+        W: io::Write,
+    {
+        w.write_all(&(self.0.len() as i32).to_le_bytes())?;
+        for (code, idx) in self.0.iter() {
+            w.write_all(&code.to_le_bytes())?;
+            idx.encode(&mut w)?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::reader::{CarReader, CarReaderV1};
+
+    #[test]
+    fn test_mh_index_sorted() {
+        let file = std::path::Path::new("test");
+        let file = file.join("carv1-basic.car");
+        let file = std::fs::File::open(file).unwrap();
+        let reader = CarReaderV1::new(file).unwrap();
+        let sections = reader.sections();
+        let index = MultihashIndexSorted::from_sections(sections.iter(), 0);
+        assert_eq!(index.len(), sections.len());
+        for s in sections.iter() {
+            assert_eq!(index.get(&s.cid()), Some(s.section_pos()));
+        }
+        let mut buf = Vec::new();
+        index.encode(&mut buf).unwrap();
+        let decoded = MultihashIndexSorted::decode(&buf[..]).unwrap();
+        assert_eq!(decoded, index);
+        let records: Vec<_> = decoded.iter().collect();
+        assert_eq!(records.len(), sections.len());
+        assert!(records
+            .windows(2)
+            .all(|w| (w[0].0, w[0].1) <= (w[1].0, w[1].1)));
+    }
+
+    #[test]
+    fn test_mh_index_sorted_layout() {
+        let digest = [1u8; 32];
+        let index = MultihashIndexSorted::from_records([(0x12, &digest[..], 59)]);
+        let mut buf = Vec::new();
+        index.encode(&mut buf).unwrap();
+        let mut expect = Vec::new();
+        expect.extend_from_slice(&1i32.to_le_bytes());
+        expect.extend_from_slice(&0x12u64.to_le_bytes());
+        expect.extend_from_slice(&1i32.to_le_bytes());
+        expect.extend_from_slice(&40u32.to_le_bytes());
+        expect.extend_from_slice(&40i64.to_le_bytes());
+        expect.extend_from_slice(&digest);
+        expect.extend_from_slice(&59u64.to_le_bytes());
+        assert_eq!(buf, expect);
+    }
+}
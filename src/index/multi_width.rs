@@ -0,0 +1,176 @@
+use std::{
+    collections::BTreeMap,
+    io::{self, Read},
+};
+
+use crate::error::CarError;
+
+use super::{read_u32_le, read_u64_le};
+
+/// the size of the offset in each record.
+const OFFSET_SIZE: usize = 8;
+
+/// the records with the same digest width, sorted by the digest.
+/// every record is the digest followed by the little endian u64 offset.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub(crate) struct SingleWidthIndex {
+    width: u32,
+    records: Vec<u8>,
+}
+
+impl SingleWidthIndex {
+    /// build the index from the (digest, offset) records, all digests must have the same length.
+    pub(crate) fn new(digest_len: usize, mut records: Vec<(&[u8], u64)>) -> Self {
+        records.sort();
+        let width = digest_len + OFFSET_SIZE;
+        let mut buf = Vec::with_capacity(width * records.len());
+        for (digest, offset) in records {
+            buf.extend_from_slice(digest);
+            buf.extend_from_slice(&offset.to_le_bytes());
+        }
+        Self {
+            width: width as u32,
+            records: buf,
+        }
+    }
+
+    #[inline(always)]
+    fn digest_len(&self) -> usize {
+        self.width as usize - OFFSET_SIZE
+    }
+
+    #[inline(always)]
+    pub(crate) fn len(&self) -> usize {
+        self.records.len() / self.width as usize
+    }
+
+    #[inline]
+    fn record(&self, i: usize) -> (&[u8], u64) {
+        let width = self.width as usize;
+        let rec = &self.records[i * width..(i + 1) * width];
+        let (digest, offset) = rec.split_at(self.digest_len());
+        let mut bs = [0u8; OFFSET_SIZE];
+        bs.copy_from_slice(offset);
+        (digest, u64::from_le_bytes(bs))
+    }
+
+    /// binary search the offset of the digest.
+    pub(crate) fn get(&self, digest: &[u8]) -> Option<u64> {
+        if digest.len() != self.digest_len() {
+            return None;
+        }
+        let (mut lo, mut hi) = (0, self.len());
+        while lo < hi {
+            let mid = (lo + hi) / 2;
+            if self.record(mid).0 < digest {
+                lo = mid + 1;
+            } else {
+                hi = mid;
+            }
+        }
+        (lo < self.len())
+            .then(|| self.record(lo))
+            .filter(|(d, _)| *d == digest)
+            .map(|(_, offset)| offset)
+    }
+
+    pub(crate) fn iter(&self) -> impl Iterator<Item = (&[u8], u64)> {
+        (0..self.len()).map(|i| self.record(i))
+    }
+
+    pub(crate) fn decode<R>(mut r: R) -> Result<Self, CarError>
+    where
+        R: io::Read,
+    {
+        let width = read_u32_le(&mut r)?;
+        let data_len = read_u64_le(&mut r)?;
+        if (width as usize) < OFFSET_SIZE || data_len % width as u64 != 0 {
+            return Err(CarError::Parsing(format!(
+                "invalid index bucket, width: {width}, length: {data_len}"
+            )));
+        }
+        let mut records = Vec::new();
+        let n = (&mut r).take(data_len).read_to_end(&mut records)?;
+        if n as u64 != data_len {
+            return Err(CarError::Parsing("index bucket is truncated".into()));
+        }
+        Ok(Self { width, records })
+    }
+
+    pub(crate) fn encode<W>(&self, mut w: W) -> Result<(), CarError>
+    where
+        W: io::Write,
+    {
+        w.write_all(&self.width.to_le_bytes())?;
+        w.write_all(&(self.records.len() as i64).to_le_bytes())?;
+        w.write_all(&self.records)?;
+        Ok(())
+    }
+}
+
+/// the index buckets grouped by the digest width.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub(crate) struct MultiWidthIndex(BTreeMap<u32, SingleWidthIndex>);
+
+impl MultiWidthIndex {
+    /// build the index from the (digest, offset) records.
+    pub(crate) fn new<'a>(records: impl IntoIterator<Item = (&'a [u8], u64)>) -> Self {
+        let mut groups: BTreeMap<usize, Vec<(&[u8], u64)>> = BTreeMap::new();
+        for (digest, offset) in records {
+            groups
+                .entry(digest.len())
+                .or_default()
+                .push((digest, offset));
+        }
+        let buckets = groups
+            .into_iter()
+            .map(|(l, recs)| {
+                let idx = SingleWidthIndex::new(l, recs);
+                (idx.width, idx)
+            })
+            .collect();
+        Self(buckets)
+    }
+
+    #[inline]
+    pub(crate) fn get(&self, digest: &[u8]) -> Option<u64> {
+        let width = (digest.len() + OFFSET_SIZE) as u32;
+        self.0.get(&width).and_then(|idx| idx.get(digest))
+    }
+
+    pub(crate) fn iter(&self) -> impl Iterator<Item = (&[u8], u64)> {
+        self.0.values().flat_map(SingleWidthIndex::iter)
+    }
+
+    #[inline]
+    pub(crate) fn len(&self) -> usize {
+        self.0.values().map(SingleWidthIndex::len).sum()
+    }
+
+    pub(crate) fn decode<R>(mut r: R) -> Result<Self, CarError>
+    where
+        R: io::Read,
+    {
+        let count = read_u32_le(&mut r)? as i32;
+        if count < 0 {
+            return Err(CarError::Parsing("invalid index bucket count".into()));
+        }
+        let mut buckets = BTreeMap::new();
+        for _ in 0..count {
+            let idx = SingleWidthIndex::decode(&mut r)?;
+            buckets.insert(idx.width, idx);
+        }
+        Ok(Self(buckets))
+    }
+
+    pub(crate) fn encode<W>(&self, mut w: W) -> Result<(), CarError>
+    where
+        W: io::Write,
+    {
+        w.write_all(&(self.0.len() as i32).to_le_bytes())?;
+        for idx in self.0.values() {
+            idx.encode(&mut w)?;
+        }
+        Ok(())
+    }
+}
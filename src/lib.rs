@@ -1,6 +1,8 @@
+pub mod blockstore;
 pub mod codec;
 pub mod error;
 pub mod header;
+pub mod index;
 mod pb;
 pub mod reader;
 pub mod section;
@@ -1,63 +1,334 @@
 use cid::Cid;
 use ipld::raw::RawCodec;
 
+mod block_iter;
+mod options;
+#[cfg(feature = "async")]
+mod reader_async;
+mod reader_lazy;
+#[cfg(feature = "mmap")]
+mod reader_mmap;
+#[cfg(any(unix, windows))]
+mod reader_shared;
+mod reader_union;
 mod reader_v1;
-use crate::{error::CarError, header::CarHeader, section::Section, unixfs::UnixFs, Ipld};
+mod reader_v2;
+use crate::{
+    error::CarError,
+    header::CarHeader,
+    index::Index,
+    section::{decode_ipld, identity_data, Section},
+    unixfs::UnixFs,
+    Ipld,
+};
 use integer_encoding::VarIntReader;
 use std::{
-    collections::VecDeque,
+    borrow::Cow,
+    collections::{HashMap, VecDeque},
     io::{self, Read, Seek},
 };
 
+pub use block_iter::BlockIterator;
+pub use options::{ReaderOptions, DEFAULT_MAX_SECTION_SIZE};
+#[cfg(feature = "async")]
+pub(crate) use reader_async::CarReaderAsync;
+#[cfg(feature = "async")]
+pub use reader_async::{
+    read_block_async, read_block_async_with, read_header_async, read_stream_header_async,
+    AsyncBlockStream, AsyncCarReader,
+};
+pub(crate) use reader_lazy::CarReaderLazy;
+#[cfg(feature = "mmap")]
+pub use reader_mmap::CarReaderMmap;
+#[cfg(any(unix, windows))]
+pub use reader_shared::CarReaderShared;
+pub use reader_union::CarReaderUnion;
 pub(crate) use reader_v1::CarReaderV1;
+pub(crate) use reader_v2::CarReaderV2;
 
-const MAX_ALLOWED_SECTION_SIZE: usize = 32 << 20;
+/// read the block with the default options.
+#[inline(always)]
+pub fn read_block<R>(reader: R) -> Result<Option<Vec<u8>>, CarError>
+where
+    R: std::io::Read,
+{
+    read_block_with(reader, &ReaderOptions::default())
+}
 
-pub fn read_block<R>(mut reader: R) -> Result<Option<Vec<u8>>, CarError>
+/// read the block, the length of the block is checked by the options.
+/// the offsets in the errors are relative to the start of the block.
+#[inline(always)]
+pub fn read_block_with<R>(reader: R, opts: &ReaderOptions) -> Result<Option<Vec<u8>>, CarError>
 where
     R: std::io::Read,
 {
-    let l: usize = match reader.read_varint() {
-        Ok(i) => i,
-        Err(e) => {
-            if e.kind() == std::io::ErrorKind::UnexpectedEof {
-                return Ok(None);
-            }
-            return Err(CarError::IO(e));
-        }
-    };
-    if l > MAX_ALLOWED_SECTION_SIZE {
-        return Err(CarError::TooLargeSection(l));
+    read_block_at(reader, opts, 0)
+}
+
+/// read the section length, none when the reader reaches the EOF before the length.
+/// the length truncated by the EOF is `UnexpectedEof`.
+/// `offset` is the position of the section, it's reported in the errors.
+fn read_len<R>(mut reader: R, offset: u64) -> Result<Option<usize>, CarError>
+where
+    R: io::Read,
+{
+    let mut first = [0u8; 1];
+    match reader.read_exact(&mut first) {
+        Ok(()) => {}
+        Err(e) if e.kind() == io::ErrorKind::UnexpectedEof => return Ok(None),
+        Err(e) => return Err(CarError::io_at(e, offset)),
     }
+    (&first[..])
+        .chain(reader)
+        .read_varint()
+        .map(Some)
+        .map_err(|e| CarError::varint_at(e, offset))
+}
+
+/// read the block which starts at `offset`, the offset is reported in the errors.
+pub(crate) fn read_block_at<R>(
+    mut reader: R,
+    opts: &ReaderOptions,
+    offset: u64,
+) -> Result<Option<Vec<u8>>, CarError>
+where
+    R: io::Read,
+{
+    let l = match read_len(&mut reader, offset)? {
+        Some(l) => l,
+        None => return Ok(None),
+    };
+    let l = match opts.check_section_len(l)? {
+        Some(l) => l,
+        None => return Ok(None),
+    };
     let mut data = vec![0u8; l];
-    reader.read_exact(&mut data[..])?;
+    reader
+        .read_exact(&mut data[..])
+        .map_err(|e| CarError::io_at(e, offset))?;
     Ok(Some(data))
 }
 
-pub(crate) fn read_section<R>(mut reader: R) -> Result<Option<Section>, CarError>
+pub(crate) fn read_section_with<R>(
+    mut reader: R,
+    opts: &ReaderOptions,
+) -> Result<Option<Section>, CarError>
 where
     R: io::Read + io::Seek,
 {
-    let len: usize = match reader.read_varint() {
-        Ok(i) => i,
-        Err(e) => {
-            if e.kind() == io::ErrorKind::UnexpectedEof {
-                return Ok(None);
-            }
-            return Err(CarError::IO(e));
-        }
+    let offset = reader.stream_position()?;
+    let len = match read_len(&mut reader, offset)? {
+        Some(l) => l,
+        None => return Ok(None),
     };
     let start = reader.stream_position()?;
-    if len > MAX_ALLOWED_SECTION_SIZE {
-        return Err(CarError::TooLargeSection(len));
-    }
-    let cid = Cid::read_bytes(&mut reader).map_err(|e| CarError::Parsing(e.to_string()))?;
+    let len = match opts.check_section_len(len)? {
+        Some(l) => l,
+        None => return Ok(None),
+    };
+    let cid = Cid::read_bytes(&mut reader).map_err(|e| match e {
+        cid::Error::Io(e) => CarError::io_at(e, offset),
+        e => CarError::InvalidCid {
+            offset: start,
+            reason: e.to_string(),
+        },
+    })?;
     let pos = reader.stream_position()?;
-    let l = len - ((pos - start) as usize);
+    let l = len
+        .checked_sub((pos - start) as usize)
+        .ok_or(CarError::InvalidCid {
+            offset: start,
+            reason: "the cid overflows the section".into(),
+        })?;
     reader.seek(io::SeekFrom::Current(l as _))?;
     Ok(Some(Section::new(cid, pos, l)))
 }
 
+/// the end of the scanned sections.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum ScanEnd {
+    /// the sections are scanned to the EOF, e.g. the CARv1 file.
+    Eof,
+    /// the end position of the CARv2 data payload.
+    At(u64),
+    /// the payload of the unfinalized CARv2 file, whose data size is 0, is scanned to the EOF.
+    /// the zeros left by the resumed blockstore end the payload.
+    Unfinalized,
+}
+
+impl ScanEnd {
+    /// the end of the sections of the file, `start` is the position of the file in the reader.
+    pub(crate) fn of(header: &CarHeader, start: u64) -> Self {
+        match header {
+            CarHeader::V2(v2) if v2.data_size > 0 => {
+                ScanEnd::At(start + v2.data_offset + v2.data_size)
+            }
+            CarHeader::V2(_) => ScanEnd::Unfinalized,
+            CarHeader::V1(_) => ScanEnd::Eof,
+        }
+    }
+
+    /// the options to read the sections, the zero length section is the EOF
+    /// of the unfinalized payload.
+    pub(crate) fn options<'a>(&self, opts: &'a ReaderOptions) -> Cow<'a, ReaderOptions> {
+        match self {
+            ScanEnd::Unfinalized => Cow::Owned(opts.clone().zero_length_section_as_eof(true)),
+            _ => Cow::Borrowed(opts),
+        }
+    }
+
+    /// the position where the sections end in the file of `file_len` bytes.
+    #[inline(always)]
+    pub(crate) fn limit(&self, file_len: u64) -> u64 {
+        match self {
+            ScanEnd::At(end) => file_len.min(*end),
+            ScanEnd::Eof | ScanEnd::Unfinalized => file_len,
+        }
+    }
+
+    /// whether the section at `pos` is after the end, `file_len` is `u64::MAX`
+    /// if the length of the stream is unknown.
+    #[inline(always)]
+    pub(crate) fn is_reached(&self, pos: u64, file_len: u64) -> bool {
+        match self {
+            ScanEnd::At(end) => pos >= *end,
+            ScanEnd::Eof | ScanEnd::Unfinalized => pos >= file_len,
+        }
+    }
+}
+
+/// check the section read at `offset` is complete, the section must end before the end
+/// and the file length. the EOF before the end of the data payload is `UnexpectedEof` too.
+/// none is the end of the sections, e.g. the zero length section as the EOF.
+pub(crate) fn complete_section(
+    section: Option<Section>,
+    offset: u64,
+    end: ScanEnd,
+    file_len: u64,
+) -> Result<Option<Section>, CarError> {
+    match section {
+        Some(s) if s.pos() + s.len() as u64 > end.limit(file_len) => {
+            Err(CarError::UnexpectedEof { offset })
+        }
+        None if offset >= file_len => Err(CarError::UnexpectedEof { offset }),
+        s => Ok(s),
+    }
+}
+
+/// read the section at the current position, none at the end of the sections.
+/// the truncated section is `UnexpectedEof`, see `complete_section`.
+pub(crate) fn read_complete_section<R>(
+    mut reader: R,
+    end: ScanEnd,
+    file_len: u64,
+    opts: &ReaderOptions,
+) -> Result<Option<Section>, CarError>
+where
+    R: io::Read + io::Seek,
+{
+    let offset = reader.stream_position()?;
+    if end.is_reached(offset, file_len) {
+        return Ok(None);
+    }
+    let section = read_section_with(&mut reader, &end.options(opts))?;
+    complete_section(section, offset, end, file_len)
+}
+
+/// the sections scanned in the file order, the duplicated cids are kept.
+#[derive(Debug, Default)]
+pub(crate) struct Scan {
+    pub(crate) sections: Vec<Section>,
+    /// the lookup index, the cid points to its first section.
+    pub(crate) index: HashMap<Cid, usize>,
+    /// the end of the last scanned section, the position of the failed section.
+    pub(crate) end: u64,
+    /// the failure which stops the scan, the sections after it are not scanned.
+    pub(crate) error: Option<CarError>,
+}
+
+impl Scan {
+    #[inline(always)]
+    pub(crate) fn new(start: u64) -> Self {
+        Self {
+            end: start,
+            ..Default::default()
+        }
+    }
+
+    pub(crate) fn push(&mut self, section: Section) {
+        self.end = section.pos() + section.len() as u64;
+        self.index
+            .entry(section.cid())
+            .or_insert(self.sections.len());
+        self.sections.push(section);
+    }
+
+    #[inline(always)]
+    pub(crate) fn get(&self, cid: &Cid) -> Option<&Section> {
+        self.index.get(cid).map(|idx| &self.sections[*idx])
+    }
+
+    /// the scan without the failure, or the failure.
+    #[inline]
+    pub(crate) fn into_result(mut self) -> Result<Self, CarError> {
+        match self.error.take() {
+            Some(e) => Err(e),
+            None => Ok(self),
+        }
+    }
+}
+
+/// scan the sections from the current position to the end.
+/// the scan stops at the first truncated or malformed section, the failure is kept in the scan.
+/// the count of the sections is checked by the options.
+pub(crate) fn scan_sections<R>(
+    mut reader: R,
+    end: ScanEnd,
+    opts: &ReaderOptions,
+) -> Result<Scan, CarError>
+where
+    R: io::Read + io::Seek,
+{
+    let mut scan = Scan::new(reader.stream_position()?);
+    let file_len = reader.seek(io::SeekFrom::End(0))?;
+    reader.seek(io::SeekFrom::Start(scan.end))?;
+    loop {
+        match read_complete_section(&mut reader, end, file_len, opts) {
+            Ok(Some(s)) => scan.push(s),
+            Ok(None) => break,
+            Err(e) => {
+                scan.error = Some(e);
+                break;
+            }
+        }
+        opts.check_sections(scan.sections.len())?;
+    }
+    Ok(scan)
+}
+
+/// the data of the cid which has no section, only the identity cid has the inlined data.
+pub(crate) fn inline_data(cid: &Cid) -> Result<Vec<u8>, CarError> {
+    identity_data(cid)
+        .map(<[u8]>::to_vec)
+        .ok_or(CarError::InvalidSection("cid not exist".into()))
+}
+
+/// the ipld of the cid which has no section, the identity cid is decoded from the inlined data.
+pub(crate) fn inline_ipld(cid: &Cid) -> Result<Ipld, CarError> {
+    match identity_data(cid) {
+        Some(data) => decode_ipld(*cid, data.to_vec()),
+        None => Err(CarError::NotFound("cid not exist".into())),
+    }
+}
+
+/// the malformed or truncated section found by the reader, see `CarReader::damage`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Damage {
+    /// the position of the damaged section, relative to the start of the file.
+    pub offset: u64,
+    pub reason: String,
+}
+
 pub trait CarReader {
     fn header(&self) -> &CarHeader;
 
@@ -67,6 +338,41 @@ pub trait CarReader {
 
     fn ipld(&mut self, cid: &Cid) -> Result<Ipld, CarError>;
 
+    /// find the section of the cid, the section data is not read.
+    fn section(&self, cid: &Cid) -> Result<Option<Section>, CarError> {
+        Ok(self.sections().into_iter().find(|s| s.cid() == *cid))
+    }
+
+    /// the damage found by the reader, e.g. in the recovery mode, by the scan of the lazy reader
+    /// or by resolving the CARv2 index. the damaged section is not indexed, neither are the
+    /// sections after it in the scanned file.
+    fn damage(&self) -> Option<&Damage> {
+        None
+    }
+
+    /// the duplicated cids with the start offsets of their sections, in the file order.
+    /// only the reader which keeps every section, e.g. the CARv1 reader, can report them.
+    fn duplicates(&self) -> Vec<(Cid, Vec<u64>)> {
+        let mut order = Vec::new();
+        let mut offsets: HashMap<Cid, Vec<u64>> = HashMap::new();
+        for s in self.sections() {
+            let entry = offsets.entry(s.cid()).or_insert_with(|| {
+                order.push(s.cid());
+                Vec::new()
+            });
+            entry.push(s.section_pos());
+        }
+        order
+            .into_iter()
+            .filter_map(|cid| {
+                offsets
+                    .remove(&cid)
+                    .filter(|o| o.len() > 1)
+                    .map(|o| (cid, o))
+            })
+            .collect()
+    }
+
     #[inline(always)]
     fn unixfs(&mut self, cid: &Cid) -> Result<UnixFs, CarError> {
         let fs_ipld = self.ipld(cid)?;
@@ -121,3 +427,186 @@ where
 {
     CarReaderV1::new(inner)
 }
+
+/// open the CARv1 reader with the options.
+#[inline(always)]
+pub fn new_v1_with_options<R>(inner: R, opts: &ReaderOptions) -> Result<impl CarReader, CarError>
+where
+    R: Read + Seek,
+{
+    CarReaderV1::new_with_options(inner, opts)
+}
+
+/// open the lazy reader, both CARv1 and CARv2 are supported.
+/// the sections are indexed on demand, the lookup only scans until the cid is found.
+#[inline(always)]
+pub fn new_lazy<R>(inner: R) -> Result<impl CarReader, CarError>
+where
+    R: Read + Seek,
+{
+    CarReaderLazy::new(inner)
+}
+
+/// open the lazy reader with the options.
+#[inline(always)]
+pub fn new_lazy_with_options<R>(inner: R, opts: &ReaderOptions) -> Result<impl CarReader, CarError>
+where
+    R: Read + Seek,
+{
+    CarReaderLazy::new_with_options(inner, opts)
+}
+
+/// open the CARv2 reader, the sections are resolved by the CARv2 index.
+#[inline(always)]
+pub fn new_v2<R>(inner: R) -> Result<impl CarReader, CarError>
+where
+    R: Read + Seek,
+{
+    CarReaderV2::new(inner)
+}
+
+/// open the CARv2 reader with the options.
+#[inline(always)]
+pub fn new_v2_with_options<R>(inner: R, opts: &ReaderOptions) -> Result<impl CarReader, CarError>
+where
+    R: Read + Seek,
+{
+    CarReaderV2::new_with_options(inner, opts)
+}
+
+/// open the CARv1 reader with the detached index, the sections are resolved by the index
+/// instead of scanning the whole file.
+#[inline(always)]
+pub fn new_v1_with_index<R>(inner: R, index: Index) -> Result<impl CarReader, CarError>
+where
+    R: Read + Seek,
+{
+    CarReaderV2::with_index(inner, index)
+}
+
+/// open the async reader, both CARv1 and CARv2 are supported, the sections are scanned.
+#[cfg(feature = "async")]
+#[inline(always)]
+pub async fn new_async<R>(inner: R) -> Result<impl AsyncCarReader, CarError>
+where
+    R: tokio::io::AsyncRead + tokio::io::AsyncSeek + Unpin + Send,
+{
+    CarReaderAsync::new_with_options(inner, &ReaderOptions::default()).await
+}
+
+/// open the async reader with the options.
+#[cfg(feature = "async")]
+#[inline(always)]
+pub async fn new_async_with_options<R>(
+    inner: R,
+    opts: &ReaderOptions,
+) -> Result<impl AsyncCarReader, CarError>
+where
+    R: tokio::io::AsyncRead + tokio::io::AsyncSeek + Unpin + Send,
+{
+    CarReaderAsync::new_with_options(inner, opts).await
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::writer::{CarWriter, CarWriterV1};
+    use std::io::Cursor;
+
+    #[test]
+    fn test_scan_sections() {
+        let data = std::fs::read("test/carv1-basic.car").unwrap();
+        let mut v1 = new_v1(Cursor::new(&data)).unwrap();
+        let sections = v1.sections();
+        let dup = sections[0].cid();
+        let mut buf = Vec::new();
+        let mut writer = CarWriterV1::new(Cursor::new(&mut buf), v1.header().clone());
+        for s in sections.iter() {
+            writer
+                .write(s.cid(), v1.read_section_data(&s.cid()).unwrap())
+                .unwrap();
+        }
+        writer.write(dup, b"dup").unwrap();
+        writer.flush().unwrap();
+
+        let opts = ReaderOptions::default();
+        let mut r = Cursor::new(&buf);
+        CarHeader::read_header(&mut r).unwrap();
+        let start = r.position();
+        let scan = scan_sections(&mut r, ScanEnd::Eof, &opts).unwrap();
+        assert!(scan.error.is_none());
+        assert_eq!(scan.sections.len(), 7);
+        assert_eq!(scan.end, buf.len() as u64);
+        assert_eq!(scan.get(&dup).unwrap().pos(), sections[0].pos());
+
+        // the data payload ends before the last section.
+        let last = scan.sections[6].section_pos();
+        r.set_position(start);
+        let scan = scan_sections(&mut r, ScanEnd::At(last), &opts).unwrap();
+        assert!(scan.error.is_none());
+        assert_eq!(scan.sections.len(), 6);
+
+        // the EOF before the end of the data payload.
+        r.set_position(start);
+        let scan = scan_sections(&mut r, ScanEnd::At(buf.len() as u64 + 1), &opts).unwrap();
+        assert_eq!(scan.sections.len(), 7);
+        assert!(matches!(
+            scan.error,
+            Some(CarError::UnexpectedEof { offset }) if offset == buf.len() as u64
+        ));
+
+        // the last section is truncated.
+        buf.truncate(buf.len() - 1);
+        let mut r = Cursor::new(&buf);
+        r.set_position(start);
+        let scan = scan_sections(&mut r, ScanEnd::Eof, &opts).unwrap();
+        assert_eq!(scan.sections.len(), 6);
+        assert_eq!(scan.end, last);
+        assert!(matches!(
+            scan.error,
+            Some(CarError::UnexpectedEof { offset }) if offset == last
+        ));
+    }
+
+    #[test]
+    fn test_unfinalized_zero_tail() {
+        let v1 = std::fs::read("test/carv1-basic.car").unwrap();
+        let mut v2 = crate::header::CarHeaderV2::new(Default::default()).encode();
+        v2.extend_from_slice(&v1);
+        // the zeros left by the resumed blockstore.
+        v2.extend_from_slice(&[0u8; 16]);
+
+        assert_eq!(new_lazy(Cursor::new(&v2)).unwrap().sections().len(), 6);
+        assert_eq!(new_v2(Cursor::new(&v2)).unwrap().sections().len(), 6);
+        let blocks: Result<Vec<_>, _> = BlockIterator::new(&v2[..]).unwrap().collect();
+        assert_eq!(blocks.unwrap().len(), 6);
+
+        let path = std::env::temp_dir().join(format!(
+            "blockless-car-{}-test_unfinalized_zero_tail.car",
+            std::process::id()
+        ));
+        std::fs::write(&path, &v2).unwrap();
+        #[cfg(any(unix, windows))]
+        let shared = CarReaderShared::open(&path).map(|r| r.sections().len());
+        #[cfg(feature = "mmap")]
+        let mmap = CarReaderMmap::open(&path).map(|mut r| r.sections().len());
+        std::fs::remove_file(&path).unwrap();
+        #[cfg(any(unix, windows))]
+        assert_eq!(shared.unwrap(), 6);
+        #[cfg(feature = "mmap")]
+        assert_eq!(mmap.unwrap(), 6);
+
+        #[cfg(feature = "async")]
+        futures::executor::block_on(async {
+            let reader = new_async(Cursor::new(&v2)).await.unwrap();
+            assert_eq!(reader.sections().len(), 6);
+            let mut stream = AsyncBlockStream::new(&v2[..]).await.unwrap();
+            let mut count = 0;
+            while let Some(block) = stream.next_block().await {
+                block.unwrap();
+                count += 1;
+            }
+            assert_eq!(count, 6);
+        });
+    }
+}
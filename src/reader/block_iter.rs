@@ -0,0 +1,137 @@
+use std::io::{self, Cursor, Read};
+
+use cid::Cid;
+
+use crate::{
+    error::CarError,
+    header::CarHeader,
+    reader::{read_block_at, ReaderOptions, ScanEnd},
+};
+
+/// the reader counts the read bytes, for the non-seekable reader.
+struct CountingReader<R> {
+    inner: R,
+    pos: u64,
+}
+
+impl<R: Read> Read for CountingReader<R> {
+    #[inline]
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        let n = self.inner.read(buf)?;
+        self.pos += n as u64;
+        Ok(n)
+    }
+}
+
+/// the forward-only block iterator, works over any `Read` e.g. stdin, pipe and socket.
+/// the blocks are yielded in the order of the file, the sections have the same
+/// size limits as `read_block`.
+pub struct BlockIterator<R> {
+    inner: CountingReader<R>,
+    header: CarHeader,
+    end: ScanEnd,
+    opts: ReaderOptions,
+    done: bool,
+}
+
+impl<R> BlockIterator<R>
+where
+    R: Read,
+{
+    #[inline(always)]
+    pub fn new(inner: R) -> Result<Self, CarError> {
+        Self::new_with_options(inner, &ReaderOptions::default())
+    }
+
+    pub fn new_with_options(inner: R, opts: &ReaderOptions) -> Result<Self, CarError> {
+        let mut inner = CountingReader { inner, pos: 0 };
+        let header = CarHeader::read_stream_header_with(&mut inner, opts)?;
+        let end = ScanEnd::of(&header, 0);
+        Ok(Self {
+            inner,
+            header,
+            end,
+            opts: end.options(opts).into_owned(),
+            done: false,
+        })
+    }
+
+    #[inline(always)]
+    pub fn header(&self) -> &CarHeader {
+        &self.header
+    }
+
+    fn next_block(&mut self) -> Result<Option<(Cid, Vec<u8>)>, CarError> {
+        // the length of the stream is unknown.
+        if self.end.is_reached(self.inner.pos, u64::MAX) {
+            return Ok(None);
+        }
+        let offset = self.inner.pos;
+        let data = match read_block_at(&mut self.inner, &self.opts, offset)? {
+            Some(data) => data,
+            None => return Ok(None),
+        };
+        let cid_offset = self.inner.pos - data.len() as u64;
+        let mut data = Cursor::new(data);
+        let cid = Cid::read_bytes(&mut data).map_err(|e| CarError::InvalidCid {
+            offset: cid_offset,
+            reason: e.to_string(),
+        })?;
+        let pos = data.position() as usize;
+        let mut data = data.into_inner();
+        data.drain(..pos);
+        Ok(Some((cid, data)))
+    }
+}
+
+impl<R> Iterator for BlockIterator<R>
+where
+    R: Read,
+{
+    type Item = Result<(Cid, Vec<u8>), CarError>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.done {
+            return None;
+        }
+        let rs = self.next_block();
+        if !matches!(rs, Ok(Some(_))) {
+            self.done = true;
+        }
+        rs.transpose()
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::index::IndexCodec;
+    use crate::utils::wrap_v1;
+
+    #[test]
+    fn test_block_iter() {
+        let file = std::path::Path::new("test");
+        let file = file.join("carv1-basic.car");
+        let v1 = std::fs::read(file).unwrap();
+        let blocks = BlockIterator::new(&v1[..])
+            .unwrap()
+            .collect::<Result<Vec<_>, _>>()
+            .unwrap();
+        assert_eq!(blocks.len(), 6);
+        let mut v2 = Vec::new();
+        wrap_v1(Cursor::new(&v1), &mut v2, IndexCodec::MultihashIndexSorted).unwrap();
+        let iter = BlockIterator::new(&v2[..]).unwrap();
+        assert_eq!(iter.header().version(), 2);
+        let v2_blocks = iter.collect::<Result<Vec<_>, _>>().unwrap();
+        assert_eq!(v2_blocks, blocks);
+
+        // the stream is cut after the first byte of the 2 bytes length.
+        let mut data = v1.clone();
+        data.extend_from_slice(&[0x80]);
+        let rs = BlockIterator::new(&data[..]).unwrap().last().unwrap();
+        assert!(matches!(
+            rs,
+            Err(CarError::UnexpectedEof { offset }) if offset == v1.len() as u64
+        ));
+    }
+}
@@ -0,0 +1,193 @@
+use crate::error::CarError;
+
+/// the default max size of the section.
+pub const DEFAULT_MAX_SECTION_SIZE: usize = 32 << 20;
+
+/// the options of the CAR reader.
+/// the default options reject the empty roots and keep the unknown header fields,
+/// the section data is not verified by `read_section_data`.
+/// the section is limited to 32MiB, the zero length section is invalid,
+/// and the count of the indexed sections is not limited.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ReaderOptions {
+    allow_empty_roots: bool,
+    allow_unknown_header_fields: bool,
+    verify_hashes: bool,
+    max_section_size: usize,
+    zero_length_section_as_eof: bool,
+    max_indexed_sections: Option<usize>,
+    recover: bool,
+}
+
+impl Default for ReaderOptions {
+    fn default() -> Self {
+        Self {
+            allow_empty_roots: false,
+            allow_unknown_header_fields: true,
+            verify_hashes: false,
+            max_section_size: DEFAULT_MAX_SECTION_SIZE,
+            zero_length_section_as_eof: false,
+            max_indexed_sections: None,
+            recover: false,
+        }
+    }
+}
+
+impl ReaderOptions {
+    /// the strict options, reject the empty roots and the unknown header fields.
+    pub fn strict() -> Self {
+        Self {
+            allow_empty_roots: false,
+            allow_unknown_header_fields: false,
+            ..Default::default()
+        }
+    }
+
+    /// the lenient options, accept the empty roots and keep the unknown header fields.
+    pub fn lenient() -> Self {
+        Self {
+            allow_empty_roots: true,
+            allow_unknown_header_fields: true,
+            ..Default::default()
+        }
+    }
+
+    #[inline(always)]
+    pub fn allow_empty_roots(mut self, allow: bool) -> Self {
+        self.allow_empty_roots = allow;
+        self
+    }
+
+    #[inline(always)]
+    pub fn allow_unknown_header_fields(mut self, allow: bool) -> Self {
+        self.allow_unknown_header_fields = allow;
+        self
+    }
+
+    /// verify the section data read by `read_section_data` against the cid.
+    /// the data decoded by `ipld` is always verified.
+    #[inline(always)]
+    pub fn verify_hashes(mut self, verify: bool) -> Self {
+        self.verify_hashes = verify;
+        self
+    }
+
+    /// the max size of the section, the larger section is rejected with `TooLargeSection`.
+    #[inline(always)]
+    pub fn max_section_size(mut self, size: usize) -> Self {
+        self.max_section_size = size;
+        self
+    }
+
+    /// treat the zero length section as the end of the file,
+    /// some tools pad the CAR file with zeros.
+    #[inline(always)]
+    pub fn zero_length_section_as_eof(mut self, eof: bool) -> Self {
+        self.zero_length_section_as_eof = eof;
+        self
+    }
+
+    /// the max count of the indexed sections, the reader fails with `TooManySections`
+    /// when the file has more sections. none is unlimited.
+    #[inline(always)]
+    pub fn max_indexed_sections(mut self, count: Option<usize>) -> Self {
+        self.max_indexed_sections = count;
+        self
+    }
+
+    /// the recovery mode, the sections are indexed until the first malformed or truncated one,
+    /// the damage is reported by the reader instead of failing.
+    #[inline(always)]
+    pub fn recover(mut self, recover: bool) -> Self {
+        self.recover = recover;
+        self
+    }
+
+    #[inline(always)]
+    pub fn is_empty_roots_allowed(&self) -> bool {
+        self.allow_empty_roots
+    }
+
+    #[inline(always)]
+    pub fn is_unknown_header_fields_allowed(&self) -> bool {
+        self.allow_unknown_header_fields
+    }
+
+    #[inline(always)]
+    pub fn is_hash_verified(&self) -> bool {
+        self.verify_hashes
+    }
+
+    #[inline(always)]
+    pub fn section_size_limit(&self) -> usize {
+        self.max_section_size
+    }
+
+    #[inline(always)]
+    pub fn is_zero_length_section_eof(&self) -> bool {
+        self.zero_length_section_as_eof
+    }
+
+    #[inline(always)]
+    pub fn indexed_sections_limit(&self) -> Option<usize> {
+        self.max_indexed_sections
+    }
+
+    #[inline(always)]
+    pub fn is_recovery_enabled(&self) -> bool {
+        self.recover
+    }
+
+    /// check the count of the indexed sections.
+    #[inline]
+    pub(crate) fn check_sections(&self, count: usize) -> Result<(), CarError> {
+        match self.max_indexed_sections {
+            Some(limit) if count > limit => Err(CarError::TooManySections(limit)),
+            _ => Ok(()),
+        }
+    }
+
+    /// check the length of the section, none if the section is the end of the file.
+    #[inline]
+    pub(crate) fn check_section_len(&self, len: usize) -> Result<Option<usize>, CarError> {
+        if len == 0 && self.zero_length_section_as_eof {
+            return Ok(None);
+        }
+        if len > self.max_section_size {
+            return Err(CarError::TooLargeSection(len));
+        }
+        Ok(Some(len))
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::reader::{self, CarReader};
+    use std::io::Cursor;
+
+    #[test]
+    fn test_reader_options() {
+        let file = std::path::Path::new("test");
+        let file = file.join("carv1-basic.car");
+        let mut data = std::fs::read(file).unwrap();
+        let opts = ReaderOptions::default().max_section_size(16);
+        assert!(matches!(
+            reader::new_v1_with_options(Cursor::new(&data), &opts),
+            Err(CarError::TooLargeSection(_))
+        ));
+        let opts = ReaderOptions::default().max_indexed_sections(Some(5));
+        assert!(matches!(
+            reader::new_v1_with_options(Cursor::new(&data), &opts),
+            Err(CarError::TooManySections(5))
+        ));
+        let opts = ReaderOptions::default().max_indexed_sections(Some(6));
+        assert!(reader::new_v1_with_options(Cursor::new(&data), &opts).is_ok());
+
+        data.extend_from_slice(&[0u8; 16]);
+        assert!(reader::new_v1(Cursor::new(&data)).is_err());
+        let opts = ReaderOptions::default().zero_length_section_as_eof(true);
+        let reader = reader::new_v1_with_options(Cursor::new(&data), &opts).unwrap();
+        assert_eq!(reader.sections().len(), 6);
+    }
+}
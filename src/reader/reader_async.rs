@@ -0,0 +1,591 @@
+use std::{
+    collections::{HashMap, VecDeque},
+    io::{self, SeekFrom},
+    pin::Pin,
+    task::{Context, Poll},
+};
+
+use async_trait::async_trait;
+use cid::Cid;
+use futures::Stream;
+use integer_encoding::{VarInt, VarIntAsyncReader};
+use ipld::raw::RawCodec;
+use tokio::io::{AsyncRead, AsyncReadExt, AsyncSeek, AsyncSeekExt, ReadBuf};
+
+use crate::{
+    error::CarError,
+    header::{CarHeader, CarHeaderV2, CARV2_HEADER_SIZE, CARV2_PRAGMA_SIZE},
+    reader::{complete_section, inline_data, inline_ipld, ReaderOptions, Scan, ScanEnd},
+    section::{decode_ipld, Section},
+    unixfs::UnixFs,
+    Ipld,
+};
+
+/// the sha2-256 multihash prefix of the CIDv0.
+const CIDV0_PREFIX: u8 = 0x12;
+const CIDV0_LEN: usize = 34;
+
+/// read the section length, none when the reader reaches the EOF before the length.
+/// the length truncated by the EOF is `UnexpectedEof`.
+/// `offset` is the position of the section, it's reported in the errors.
+async fn read_len_async<R>(
+    reader: &mut R,
+    opts: &ReaderOptions,
+    offset: u64,
+) -> Result<Option<usize>, CarError>
+where
+    R: AsyncRead + Unpin + Send,
+{
+    let first = match reader.read_u8().await {
+        Ok(b) => [b],
+        Err(e) if e.kind() == io::ErrorKind::UnexpectedEof => return Ok(None),
+        Err(e) => return Err(CarError::io_at(e, offset)),
+    };
+    let l: usize = (&first[..])
+        .chain(reader)
+        .read_varint_async()
+        .await
+        .map_err(|e| CarError::varint_at(e, offset))?;
+    opts.check_section_len(l)
+}
+
+/// read the varint and append the raw bytes to the buffer.
+async fn read_varint_bytes<R>(reader: &mut R, buf: &mut Vec<u8>) -> io::Result<u64>
+where
+    R: AsyncRead + Unpin + Send,
+{
+    let start = buf.len();
+    loop {
+        let b = reader.read_u8().await?;
+        buf.push(b);
+        if b & 0x80 == 0 {
+            break;
+        }
+        if buf.len() - start >= 10 {
+            break;
+        }
+    }
+    u64::decode_var(&buf[start..])
+        .map(|(v, _)| v)
+        .ok_or(io::Error::new(io::ErrorKind::InvalidData, "invalid varint"))
+}
+
+/// read the cid of the section at `offset`, the cid is no longer than `limit` bytes.
+async fn read_cid_async<R>(
+    reader: &mut R,
+    limit: usize,
+    offset: u64,
+    cid_offset: u64,
+) -> Result<(Cid, usize), CarError>
+where
+    R: AsyncRead + Unpin + Send,
+{
+    let invalid_cid = |reason: String| CarError::InvalidCid {
+        offset: cid_offset,
+        reason,
+    };
+    let cid_err = |e: io::Error| match e.kind() {
+        io::ErrorKind::InvalidData => invalid_cid(e.to_string()),
+        _ => CarError::io_at(e, offset),
+    };
+    let mut buf = Vec::with_capacity(CIDV0_LEN);
+    let version = read_varint_bytes(reader, &mut buf).await.map_err(cid_err)?;
+    let digest_len = if version == CIDV0_PREFIX as u64 {
+        CIDV0_LEN - buf.len()
+    } else {
+        // codec, multihash code and multihash digest size.
+        read_varint_bytes(reader, &mut buf).await.map_err(cid_err)?;
+        read_varint_bytes(reader, &mut buf).await.map_err(cid_err)?;
+        read_varint_bytes(reader, &mut buf).await.map_err(cid_err)? as usize
+    };
+    let start = buf.len();
+    let cid_len = match start.checked_add(digest_len) {
+        Some(n) if n <= limit => n,
+        _ => return Err(invalid_cid("the cid overflows the section".into())),
+    };
+    buf.resize(cid_len, 0);
+    reader
+        .read_exact(&mut buf[start..])
+        .await
+        .map_err(|e| CarError::io_at(e, offset))?;
+    let cid = Cid::try_from(&buf[..]).map_err(|e| invalid_cid(e.to_string()))?;
+    Ok((cid, buf.len()))
+}
+
+/// read the block from the async reader, the async counterpart of `read_block`.
+#[inline(always)]
+pub async fn read_block_async<R>(reader: R) -> Result<Option<Vec<u8>>, CarError>
+where
+    R: AsyncRead + Unpin + Send,
+{
+    read_block_async_with(reader, &ReaderOptions::default()).await
+}
+
+/// read the block from the async reader, the length of the block is checked by the options.
+/// the offsets in the errors are relative to the start of the block.
+#[inline(always)]
+pub async fn read_block_async_with<R>(
+    reader: R,
+    opts: &ReaderOptions,
+) -> Result<Option<Vec<u8>>, CarError>
+where
+    R: AsyncRead + Unpin + Send,
+{
+    read_block_async_at(reader, opts, 0).await
+}
+
+/// read the block which starts at `offset`, the offset is reported in the errors.
+async fn read_block_async_at<R>(
+    mut reader: R,
+    opts: &ReaderOptions,
+    offset: u64,
+) -> Result<Option<Vec<u8>>, CarError>
+where
+    R: AsyncRead + Unpin + Send,
+{
+    let l = match read_len_async(&mut reader, opts, offset).await? {
+        Some(l) => l,
+        None => return Ok(None),
+    };
+    let mut data = vec![0u8; l];
+    reader
+        .read_exact(&mut data[..])
+        .await
+        .map_err(|e| CarError::io_at(e, offset))?;
+    Ok(Some(data))
+}
+
+/// read the section from the async reader, the section data is skipped by seeking.
+pub(crate) async fn read_section_async<R>(
+    mut reader: R,
+    opts: &ReaderOptions,
+) -> Result<Option<Section>, CarError>
+where
+    R: AsyncRead + AsyncSeek + Unpin + Send,
+{
+    let offset = reader.stream_position().await?;
+    let len = match read_len_async(&mut reader, opts, offset).await? {
+        Some(l) => l,
+        None => return Ok(None),
+    };
+    let cid_offset = reader.stream_position().await?;
+    let (cid, cid_len) = read_cid_async(&mut reader, len, offset, cid_offset).await?;
+    let pos = reader.stream_position().await?;
+    let l = len - cid_len;
+    reader.seek(SeekFrom::Current(l as _)).await?;
+    Ok(Some(Section::new(cid, pos, l)))
+}
+
+/// read the section at the current position, the async counterpart of `read_complete_section`.
+async fn read_complete_section_async<R>(
+    reader: &mut R,
+    end: ScanEnd,
+    file_len: u64,
+    opts: &ReaderOptions,
+) -> Result<Option<Section>, CarError>
+where
+    R: AsyncRead + AsyncSeek + Unpin + Send,
+{
+    let offset = reader.stream_position().await?;
+    if end.is_reached(offset, file_len) {
+        return Ok(None);
+    }
+    let section = read_section_async(&mut *reader, &end.options(opts)).await?;
+    complete_section(section, offset, end, file_len)
+}
+
+/// scan the sections from the current position to the end, the async counterpart of `scan_sections`.
+async fn scan_sections_async<R>(
+    reader: &mut R,
+    end: ScanEnd,
+    opts: &ReaderOptions,
+) -> Result<Scan, CarError>
+where
+    R: AsyncRead + AsyncSeek + Unpin + Send,
+{
+    let mut scan = Scan::new(reader.stream_position().await?);
+    let file_len = reader.seek(SeekFrom::End(0)).await?;
+    reader.seek(SeekFrom::Start(scan.end)).await?;
+    loop {
+        match read_complete_section_async(reader, end, file_len, opts).await {
+            Ok(Some(s)) => scan.push(s),
+            Ok(None) => break,
+            Err(e) => {
+                scan.error = Some(e);
+                break;
+            }
+        }
+        opts.check_sections(scan.sections.len())?;
+    }
+    Ok(scan)
+}
+
+/// read the header from the non-seekable async reader, the header is checked by the options.
+/// for the CARv2 file, the padding before the data payload is skipped by reading,
+/// the index codec is not read.
+pub async fn read_stream_header_async<R>(
+    mut r: R,
+    opts: &ReaderOptions,
+) -> Result<CarHeader, CarError>
+where
+    R: AsyncRead + Unpin + Send,
+{
+    let data = match read_block_async_at(&mut r, opts, 0).await? {
+        Some(d) => d,
+        None => return Err(CarError::Parsing("Invalid Header".into())),
+    };
+    if let Some(header) = CarHeader::decode_first_block(&data, opts)? {
+        return Ok(header);
+    }
+    let mut buf = [0u8; CARV2_HEADER_SIZE];
+    r.read_exact(&mut buf)
+        .await
+        .map_err(|e| CarError::io_at(e, CARV2_PRAGMA_SIZE as u64))?;
+    let v2 = CarHeaderV2::decode(&buf, Default::default())?;
+    let padding = v2
+        .data_offset
+        .checked_sub((CARV2_PRAGMA_SIZE + CARV2_HEADER_SIZE) as u64)
+        .ok_or(CarError::InvalidFile(
+            "the data offset overlaps the header".into(),
+        ))?;
+    if tokio::io::copy(&mut (&mut r).take(padding), &mut tokio::io::sink()).await? != padding {
+        return Err(CarError::UnexpectedEof {
+            offset: (CARV2_PRAGMA_SIZE + CARV2_HEADER_SIZE) as u64,
+        });
+    }
+    let data = match read_block_async_at(&mut r, opts, v2.data_offset).await? {
+        Some(d) => d,
+        None => return Err(CarError::Parsing("Invalid inner Header".into())),
+    };
+    let inner = match CarHeader::decode_with(&data[..], opts)? {
+        CarHeader::V1(v1) => v1,
+        CarHeader::V2(_) => unreachable!("decode only support CARv1 header"),
+    };
+    Ok(CarHeader::V2(CarHeaderV2 { inner, ..v2 }))
+}
+
+/// read the header from the async reader, the async counterpart of `CarHeader::read_header_with`.
+/// the reader will be positioned after the inner CARv1 header, the index codec is not read.
+pub async fn read_header_async<R>(mut r: R, opts: &ReaderOptions) -> Result<CarHeader, CarError>
+where
+    R: AsyncRead + AsyncSeek + Unpin + Send,
+{
+    read_stream_header_async(&mut r, opts).await
+}
+
+/// the async counterpart of `CarReader`.
+#[async_trait]
+pub trait AsyncCarReader: Send {
+    fn header(&self) -> &CarHeader;
+
+    fn sections(&self) -> Vec<Section>;
+
+    async fn read_section_data(&mut self, cid: &Cid) -> Result<Vec<u8>, CarError>;
+
+    async fn ipld(&mut self, cid: &Cid) -> Result<Ipld, CarError>;
+
+    #[inline(always)]
+    async fn unixfs(&mut self, cid: &Cid) -> Result<UnixFs, CarError> {
+        let fs_ipld = self.ipld(cid).await?;
+        (*cid, fs_ipld).try_into()
+    }
+
+    async fn search_file_cid(&mut self, f: &str) -> Result<Cid, CarError> {
+        let raw_code: u64 = RawCodec.into();
+        for root in self.header().roots() {
+            let mut searchq = VecDeque::from([root]);
+            while let Some(cid) = searchq.pop_front() {
+                if cid.codec() == raw_code {
+                    continue;
+                }
+                let fs_ipld = self.ipld(&cid).await?;
+                if matches!(fs_ipld, Ipld::Map(_)) {
+                    let unixfs: UnixFs = (cid, fs_ipld).try_into()?;
+                    for ufs in unixfs.links() {
+                        if ufs.name_ref() == f {
+                            return Ok(ufs.hash);
+                        }
+                        searchq.push_back(ufs.hash);
+                    }
+                }
+            }
+        }
+        Err(CarError::NotFound(format!("search {f} fail.")))
+    }
+}
+
+/// the async reader scans the sections of the CARv1 file or the CARv2 data payload.
+/// the sections are kept in the file order, the lookup index points to the first section of the cid.
+pub(crate) struct CarReaderAsync<R> {
+    inner: R,
+    header: CarHeader,
+    sections: Vec<Section>,
+    index: HashMap<Cid, usize>,
+    opts: ReaderOptions,
+}
+
+impl<R> CarReaderAsync<R>
+where
+    R: AsyncRead + AsyncSeek + Unpin + Send,
+{
+    pub(crate) async fn new_with_options(
+        mut inner: R,
+        opts: &ReaderOptions,
+    ) -> Result<Self, CarError> {
+        let start = inner.stream_position().await?;
+        let header = read_header_async(&mut inner, opts).await?;
+        let end = ScanEnd::of(&header, start);
+        let scan = scan_sections_async(&mut inner, end, opts)
+            .await?
+            .into_result()?;
+        Ok(Self {
+            inner,
+            header,
+            sections: scan.sections,
+            index: scan.index,
+            opts: opts.clone(),
+        })
+    }
+
+    #[inline(always)]
+    fn lookup(&self, cid: &Cid) -> Option<Section> {
+        self.index.get(cid).map(|idx| self.sections[*idx].clone())
+    }
+
+    async fn read_data(&mut self, section: &Section) -> Result<Vec<u8>, CarError> {
+        self.inner.seek(SeekFrom::Start(section.pos())).await?;
+        let mut buf = vec![0u8; section.len()];
+        self.inner
+            .read_exact(&mut buf)
+            .await
+            .map_err(|e| CarError::io_at(e, section.section_pos()))?;
+        Ok(buf)
+    }
+}
+
+#[async_trait]
+impl<R> AsyncCarReader for CarReaderAsync<R>
+where
+    R: AsyncRead + AsyncSeek + Unpin + Send,
+{
+    #[inline(always)]
+    fn header(&self) -> &CarHeader {
+        &self.header
+    }
+
+    /// all the sections in the file order, include the duplicated ones.
+    #[inline(always)]
+    fn sections(&self) -> Vec<Section> {
+        self.sections.clone()
+    }
+
+    async fn read_section_data(&mut self, cid: &Cid) -> Result<Vec<u8>, CarError> {
+        let s = match self.lookup(cid) {
+            Some(s) => s,
+            None => return inline_data(cid),
+        };
+        let data = self.read_data(&s).await?;
+        if self.opts.is_hash_verified() {
+            s.verify(&data)?;
+        }
+        Ok(data)
+    }
+
+    async fn ipld(&mut self, cid: &Cid) -> Result<Ipld, CarError> {
+        let s = match self.lookup(cid) {
+            Some(s) => s,
+            None => return inline_ipld(cid),
+        };
+        let data = self.read_data(&s).await?;
+        s.verify(&data)?;
+        decode_ipld(*cid, data)
+    }
+}
+
+/// the async reader counts the read bytes, for the non-seekable reader.
+struct CountingReader<R> {
+    inner: R,
+    pos: u64,
+}
+
+impl<R: AsyncRead + Unpin> AsyncRead for CountingReader<R> {
+    fn poll_read(
+        mut self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+        buf: &mut ReadBuf<'_>,
+    ) -> Poll<io::Result<()>> {
+        let filled = buf.filled().len();
+        let rs = Pin::new(&mut self.inner).poll_read(cx, buf);
+        if let Poll::Ready(Ok(())) = rs {
+            self.pos += (buf.filled().len() - filled) as u64;
+        }
+        rs
+    }
+}
+
+/// the forward-only async block stream, the async counterpart of `BlockIterator`.
+/// works over any `AsyncRead` e.g. tcp stream and http body.
+pub struct AsyncBlockStream<R> {
+    inner: CountingReader<R>,
+    header: CarHeader,
+    end: ScanEnd,
+    opts: ReaderOptions,
+    done: bool,
+}
+
+impl<R> AsyncBlockStream<R>
+where
+    R: AsyncRead + Unpin + Send,
+{
+    #[inline(always)]
+    pub async fn new(inner: R) -> Result<Self, CarError> {
+        Self::new_with_options(inner, &ReaderOptions::default()).await
+    }
+
+    pub async fn new_with_options(inner: R, opts: &ReaderOptions) -> Result<Self, CarError> {
+        let mut inner = CountingReader { inner, pos: 0 };
+        let header = read_stream_header_async(&mut inner, opts).await?;
+        let end = ScanEnd::of(&header, 0);
+        Ok(Self {
+            inner,
+            header,
+            end,
+            opts: end.options(opts).into_owned(),
+            done: false,
+        })
+    }
+
+    #[inline(always)]
+    pub fn header(&self) -> &CarHeader {
+        &self.header
+    }
+
+    async fn read_next(&mut self) -> Result<Option<(Cid, Vec<u8>)>, CarError> {
+        // the length of the stream is unknown.
+        if self.end.is_reached(self.inner.pos, u64::MAX) {
+            return Ok(None);
+        }
+        let offset = self.inner.pos;
+        let len = match read_len_async(&mut self.inner, &self.opts, offset).await? {
+            Some(l) => l,
+            None => return Ok(None),
+        };
+        let cid_offset = self.inner.pos;
+        let (cid, cid_len) = read_cid_async(&mut self.inner, len, offset, cid_offset).await?;
+        let mut data = vec![0u8; len - cid_len];
+        self.inner
+            .read_exact(&mut data)
+            .await
+            .map_err(|e| CarError::io_at(e, offset))?;
+        Ok(Some((cid, data)))
+    }
+
+    /// read the next block, none when the stream is finished.
+    /// the stream is finished after the first error.
+    pub async fn next_block(&mut self) -> Option<Result<(Cid, Vec<u8>), CarError>> {
+        if self.done {
+            return None;
+        }
+        let rs = self.read_next().await;
+        if !matches!(rs, Ok(Some(_))) {
+            self.done = true;
+        }
+        rs.transpose()
+    }
+
+    /// convert into the `Stream` of the blocks.
+    pub fn into_stream(self) -> impl Stream<Item = Result<(Cid, Vec<u8>), CarError>> {
+        futures::stream::unfold(self, |mut s| async move {
+            s.next_block().await.map(|item| (item, s))
+        })
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::index::IndexCodec;
+    use crate::reader::{self, CarReader};
+    use crate::utils::wrap_v1;
+    use futures::{executor::block_on, StreamExt};
+    use std::io::Cursor;
+
+    #[test]
+    fn test_async_reader() {
+        let file = std::path::Path::new("test");
+        let file = file.join("carv1-basic.car");
+        let v1 = std::fs::read(file).unwrap();
+        let mut v2 = Vec::new();
+        wrap_v1(Cursor::new(&v1), &mut v2, IndexCodec::MultihashIndexSorted).unwrap();
+        let mut sync_reader = reader::new_v1(Cursor::new(&v1)).unwrap();
+        block_on(async {
+            for buf in [&v1, &v2] {
+                let mut reader = reader::new_async(Cursor::new(buf)).await.unwrap();
+                assert_eq!(reader.header().roots(), sync_reader.header().roots());
+                assert_eq!(reader.sections().len(), 6);
+                for s in sync_reader.sections() {
+                    let data = reader.read_section_data(&s.cid()).await.unwrap();
+                    assert_eq!(data, sync_reader.read_section_data(&s.cid()).unwrap());
+                    let ipld = reader.ipld(&s.cid()).await.unwrap();
+                    assert_eq!(ipld, sync_reader.ipld(&s.cid()).unwrap());
+                }
+                let cid = reader.search_file_cid("not-distributed.jpg").await;
+                assert_eq!(
+                    cid.ok(),
+                    sync_reader.search_file_cid("not-distributed.jpg").ok()
+                );
+
+                let stream = AsyncBlockStream::new(&buf[..]).await.unwrap();
+                let blocks: Vec<_> = stream.into_stream().collect().await;
+                assert_eq!(blocks.len(), 6);
+                for block in blocks {
+                    let (cid, data) = block.unwrap();
+                    assert_eq!(data, sync_reader.read_section_data(&cid).unwrap());
+                }
+            }
+        });
+    }
+
+    #[test]
+    fn test_async_reader_malformed_cid() {
+        let file = std::path::Path::new("test");
+        let file = file.join("carv1-basic.car");
+        let v1 = std::fs::read(file).unwrap();
+        let (header_len, n) = u64::decode_var(&v1).unwrap();
+        let mut buf = v1[..n + header_len as usize].to_vec();
+        let cid_offset = buf.len() as u64 + 1;
+        // the cid with the digest size of u64::MAX.
+        let mut section = vec![0x01, 0x55, 0x12];
+        section.extend_from_slice(&[0xff; 9]);
+        section.push(0x01);
+        buf.push(section.len() as u8);
+        buf.extend_from_slice(&section);
+        block_on(async {
+            let rs = reader::new_async(Cursor::new(&buf)).await;
+            assert!(matches!(
+                rs.err(),
+                Some(CarError::InvalidCid { offset, .. }) if offset == cid_offset
+            ));
+            let mut stream = AsyncBlockStream::new(&buf[..]).await.unwrap();
+            let rs = stream.next_block().await.unwrap();
+            assert!(matches!(
+                rs,
+                Err(CarError::InvalidCid { offset, .. }) if offset == cid_offset
+            ));
+            assert!(stream.next_block().await.is_none());
+
+            // the stream is cut after the first byte of the 2 bytes length.
+            let mut data = v1.clone();
+            data.push(0x80);
+            let mut stream = AsyncBlockStream::new(&data[..]).await.unwrap();
+            let mut last = None;
+            while let Some(rs) = stream.next_block().await {
+                last = Some(rs);
+            }
+            assert!(matches!(
+                last,
+                Some(Err(CarError::UnexpectedEof { offset })) if offset == v1.len() as u64
+            ));
+        });
+    }
+}
@@ -0,0 +1,194 @@
+use cid::Cid;
+
+use crate::{
+    error::CarError,
+    header::CarHeader,
+    reader::{CarReader, Damage, ReaderOptions},
+    section::Section,
+    Ipld,
+};
+use std::{
+    cell::{OnceCell, RefCell},
+    io::{Read, Seek, SeekFrom},
+};
+
+use super::{inline_data, inline_ipld, read_complete_section, Scan, ScanEnd};
+
+/// the sections which are passed by the lookups.
+struct LazyIndex {
+    /// the scanned sections, the next section to scan is at the end of the scan.
+    scan: Scan,
+    end: ScanEnd,
+    file_len: u64,
+    done: bool,
+}
+
+/// the lazy reader, the sections are indexed incrementally.
+/// the lookup scans forward only until the cid is found, and remembers every section it passes,
+/// so the blocks at the front of the file are served without scanning the whole file.
+pub(crate) struct CarReaderLazy<R> {
+    inner: RefCell<R>,
+    header: CarHeader,
+    index: RefCell<LazyIndex>,
+    /// the first scan failure of `sections`, which can't return the error.
+    damage: OnceCell<Damage>,
+    opts: ReaderOptions,
+}
+
+impl<R> CarReaderLazy<R>
+where
+    R: Read + Seek,
+{
+    #[inline(always)]
+    pub(crate) fn new(inner: R) -> Result<Self, CarError> {
+        Self::new_with_options(inner, &ReaderOptions::default())
+    }
+
+    pub(crate) fn new_with_options(mut inner: R, opts: &ReaderOptions) -> Result<Self, CarError> {
+        let start = inner.stream_position()?;
+        let header = CarHeader::read_header_with(&mut inner, opts)?;
+        let index = LazyIndex {
+            scan: Scan::new(inner.stream_position()?),
+            end: ScanEnd::of(&header, start),
+            file_len: inner.seek(SeekFrom::End(0))?,
+            done: false,
+        };
+        Ok(Self {
+            inner: RefCell::new(inner),
+            header,
+            index: RefCell::new(index),
+            damage: OnceCell::new(),
+            opts: opts.clone(),
+        })
+    }
+
+    /// find the section of the cid, the unscanned sections are scanned until the cid is found.
+    /// scan all the rest sections if the cid is none.
+    fn scan_until(&self, cid: Option<&Cid>) -> Result<Option<Section>, CarError> {
+        let mut index = self.index.borrow_mut();
+        if let Some(s) = cid.and_then(|cid| index.scan.get(cid)) {
+            return Ok(Some(s.clone()));
+        }
+        let mut inner = self.inner.borrow_mut();
+        inner.seek(SeekFrom::Start(index.scan.end))?;
+        while !index.done {
+            let next = read_complete_section(&mut *inner, index.end, index.file_len, &self.opts)?;
+            let s = match next {
+                Some(s) => s,
+                None => {
+                    index.done = true;
+                    break;
+                }
+            };
+            let found = cid == Some(&s.cid());
+            index.scan.push(s.clone());
+            self.opts.check_sections(index.scan.sections.len())?;
+            if found {
+                return Ok(Some(s));
+            }
+        }
+        Ok(None)
+    }
+}
+
+impl<R> CarReader for CarReaderLazy<R>
+where
+    R: Read + Seek,
+{
+    #[inline(always)]
+    fn header(&self) -> &CarHeader {
+        &self.header
+    }
+
+    #[inline(always)]
+    fn section(&self, cid: &Cid) -> Result<Option<Section>, CarError> {
+        self.scan_until(Some(cid))
+    }
+
+    /// all the sections in the file order, the rest of the file is scanned.
+    /// the sections after the malformed one are skipped, the failure is reported by `damage`.
+    fn sections(&self) -> Vec<Section> {
+        if let Err(e) = self.scan_until(None) {
+            let offset = self.index.borrow().scan.end;
+            let _ = self.damage.set(Damage {
+                offset,
+                reason: e.to_string(),
+            });
+        }
+        self.index.borrow().scan.sections.clone()
+    }
+
+    #[inline(always)]
+    fn damage(&self) -> Option<&Damage> {
+        self.damage.get()
+    }
+
+    #[inline]
+    fn read_section_data(&mut self, cid: &Cid) -> Result<Vec<u8>, CarError> {
+        let s = match self.scan_until(Some(cid))? {
+            Some(s) => s,
+            None => return inline_data(cid),
+        };
+        let data = s.read_data(self.inner.get_mut())?;
+        if self.opts.is_hash_verified() {
+            s.verify(&data)?;
+        }
+        Ok(data)
+    }
+
+    #[inline]
+    fn ipld(&mut self, cid: &Cid) -> Result<Ipld, CarError> {
+        let mut s = match self.scan_until(Some(cid))? {
+            Some(s) => s,
+            None => return inline_ipld(cid),
+        };
+        s.ipld(self.inner.get_mut())
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::reader::CarReaderV1;
+    use crate::unixfs::UnixFs;
+
+    #[test]
+    fn test_lazy_read() {
+        let file = std::path::Path::new("test");
+        let file = file.join("carv1-basic.car");
+        let file = std::fs::File::open(file).unwrap();
+        let mut reader = CarReaderLazy::new(file).unwrap();
+        let roots = reader.header().roots();
+        let unix_fs: UnixFs = reader.ipld(&roots[0]).unwrap().try_into().unwrap();
+        assert_eq!(unix_fs.links.len(), 3);
+        let scanned = reader.index.borrow().scan.sections.len();
+        assert!(scanned < 6);
+        assert_eq!(reader.sections().len(), 6);
+
+        let file = std::fs::File::open("test/carv1-basic.car").unwrap();
+        let mut v1 = CarReaderV1::new(file).unwrap();
+        for s in v1.sections() {
+            assert_eq!(
+                reader.read_section_data(&s.cid()).unwrap(),
+                v1.read_section_data(&s.cid()).unwrap()
+            );
+        }
+        let missing = crate::utils::raw_cid(b"missing");
+        assert!(matches!(reader.ipld(&missing), Err(CarError::NotFound(_))));
+        assert!(reader.damage().is_none());
+    }
+
+    #[test]
+    fn test_lazy_damage() {
+        let mut data = std::fs::read("test/carv1-basic.car").unwrap();
+        let valid = data.len() as u64;
+        // the section claims more bytes than the file has.
+        data.extend_from_slice(&[0x80, 0x01, 0x01]);
+        let reader = CarReaderLazy::new(std::io::Cursor::new(data)).unwrap();
+        assert_eq!(reader.sections().len(), 6);
+        let damage = reader.damage().unwrap();
+        assert_eq!(damage.offset, valid);
+        let missing = crate::utils::raw_cid(b"missing");
+        assert!(reader.section(&missing).is_err());
+    }
+}
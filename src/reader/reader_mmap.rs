@@ -0,0 +1,208 @@
+use std::{collections::HashMap, fs::File, io::Cursor, path::Path};
+
+use bytes::Bytes;
+use cid::Cid;
+use memmap2::Mmap;
+
+use crate::{
+    error::CarError,
+    header::CarHeader,
+    reader::{inline_data, inline_ipld, scan_sections, CarReader, ReaderOptions, ScanEnd},
+    section::{decode_ipld, Section},
+    Ipld,
+};
+
+/// the memory-mapped reader, both CARv1 and CARv2 are supported.
+/// the block data are borrowed from the mapped file by `block` or shared by `block_bytes`,
+/// no buffer is allocated for the block data.
+/// the sections are kept in the file order, the lookup index points to the first section of the cid.
+pub struct CarReaderMmap {
+    data: Bytes,
+    header: CarHeader,
+    sections: Vec<Section>,
+    index: HashMap<Cid, usize>,
+    opts: ReaderOptions,
+}
+
+impl CarReaderMmap {
+    /// map the file and scan the sections.
+    #[inline]
+    pub fn open<P: AsRef<Path>>(path: P) -> Result<Self, CarError> {
+        Self::from_file(&File::open(path)?)
+    }
+
+    /// map the file and scan the sections.
+    /// the file should not be modified while it's mapped.
+    #[inline]
+    pub fn from_file(file: &File) -> Result<Self, CarError> {
+        Self::from_file_with_options(file, &ReaderOptions::default())
+    }
+
+    /// map the file and scan the sections, the header is checked by the options.
+    pub fn from_file_with_options(file: &File, opts: &ReaderOptions) -> Result<Self, CarError> {
+        // Safety: the mapped file is read only, the caller should not modify the file
+        // while the reader is alive.
+        let mmap = unsafe { Mmap::map(file)? };
+        Self::from_bytes_with_options(Bytes::from_owner(mmap), opts)
+    }
+
+    /// scan the sections of the CAR file in the memory.
+    #[inline]
+    pub fn from_bytes(data: Bytes) -> Result<Self, CarError> {
+        Self::from_bytes_with_options(data, &ReaderOptions::default())
+    }
+
+    pub fn from_bytes_with_options(data: Bytes, opts: &ReaderOptions) -> Result<Self, CarError> {
+        let mut cursor = Cursor::new(&data[..]);
+        let header = CarHeader::read_header_with(&mut cursor, opts)?;
+        let scan = scan_sections(&mut cursor, ScanEnd::of(&header, 0), opts)?.into_result()?;
+        Ok(Self {
+            data,
+            header,
+            sections: scan.sections,
+            index: scan.index,
+            opts: opts.clone(),
+        })
+    }
+
+    #[inline(always)]
+    fn lookup(&self, cid: &Cid) -> Option<&Section> {
+        self.index.get(cid).map(|idx| &self.sections[*idx])
+    }
+
+    /// the block data verified by the cid, the offset of the mismatch error is the start of the section.
+    pub fn verified_block(&self, cid: &Cid) -> Result<Option<&[u8]>, CarError> {
+        let (s, data) = match self.lookup(cid).zip(self.block(cid)) {
+            Some(v) => v,
+            None => return Ok(None),
+        };
+        s.verify(data)?;
+        Ok(Some(data))
+    }
+
+    /// the block data borrowed from the mapped file, the data is not verified.
+    pub fn block(&self, cid: &Cid) -> Option<&[u8]> {
+        let s = self.lookup(cid)?;
+        let pos = s.pos() as usize;
+        Some(&self.data[pos..pos + s.len()])
+    }
+
+    /// the block data shared with the mapped file, the mapping is kept alive by the bytes.
+    pub fn block_bytes(&self, cid: &Cid) -> Option<Bytes> {
+        let s = self.lookup(cid)?;
+        let pos = s.pos() as usize;
+        Some(self.data.slice(pos..pos + s.len()))
+    }
+}
+
+impl CarReader for CarReaderMmap {
+    #[inline(always)]
+    fn header(&self) -> &CarHeader {
+        &self.header
+    }
+
+    #[inline]
+    fn section(&self, cid: &Cid) -> Result<Option<Section>, CarError> {
+        Ok(self.lookup(cid).cloned())
+    }
+
+    /// all the sections in the file order, include the duplicated ones.
+    #[inline(always)]
+    fn sections(&self) -> Vec<Section> {
+        self.sections.clone()
+    }
+
+    #[inline]
+    fn read_section_data(&mut self, cid: &Cid) -> Result<Vec<u8>, CarError> {
+        let data = if self.opts.is_hash_verified() {
+            self.verified_block(cid)?
+        } else {
+            self.block(cid)
+        };
+        match data {
+            Some(data) => Ok(data.to_vec()),
+            None => inline_data(cid),
+        }
+    }
+
+    #[inline]
+    fn ipld(&mut self, cid: &Cid) -> Result<Ipld, CarError> {
+        match self.verified_block(cid)? {
+            Some(data) => decode_ipld(*cid, data.to_vec()),
+            None => inline_ipld(cid),
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::index::IndexCodec;
+    use crate::reader::CarReaderV1;
+    use crate::utils::wrap_v1;
+    use crate::writer::{CarWriter, CarWriterV1};
+
+    #[test]
+    fn test_mmap_read() {
+        let file = std::path::Path::new("test");
+        let file = file.join("carv1-basic.car");
+        let mut reader = CarReaderMmap::open(&file).unwrap();
+        let mut v1 = CarReaderV1::new(File::open(&file).unwrap()).unwrap();
+        assert_eq!(reader.header().roots(), v1.header().roots());
+        assert_eq!(reader.sections().len(), 6);
+        for s in v1.sections() {
+            let data = v1.read_section_data(&s.cid()).unwrap();
+            assert_eq!(reader.block(&s.cid()).unwrap(), &data[..]);
+            assert_eq!(reader.block_bytes(&s.cid()).unwrap(), data);
+            assert_eq!(reader.ipld(&s.cid()).unwrap(), v1.ipld(&s.cid()).unwrap());
+        }
+        let root = reader.header().roots()[0];
+        assert!(reader.search_file_cid("not-distributed.jpg").is_ok());
+
+        let mut v2 = Vec::new();
+        let v1_data = std::fs::read(&file).unwrap();
+        wrap_v1(
+            Cursor::new(&v1_data),
+            &mut v2,
+            IndexCodec::MultihashIndexSorted,
+        )
+        .unwrap();
+        let v2_reader = CarReaderMmap::from_bytes(Bytes::from(v2)).unwrap();
+        assert_eq!(v2_reader.header().version(), 2);
+        assert_eq!(v2_reader.block(&root), reader.block(&root));
+    }
+
+    #[test]
+    fn test_mmap_duplicates() {
+        let data = std::fs::read("test/carv1-basic.car").unwrap();
+        let mut v1 = CarReaderV1::new(Cursor::new(&data)).unwrap();
+        let sections = v1.sections();
+        let dup = sections[0].cid();
+        let mut buf = Vec::new();
+        let mut writer = CarWriterV1::new(Cursor::new(&mut buf), v1.header().clone());
+        for s in sections.iter() {
+            writer
+                .write(s.cid(), v1.read_section_data(&s.cid()).unwrap())
+                .unwrap();
+        }
+        writer.write(dup, b"dup").unwrap();
+        writer.flush().unwrap();
+        let reader = CarReaderMmap::from_bytes(Bytes::from(buf.clone())).unwrap();
+        let scanned = reader.sections();
+        assert_eq!(scanned.len(), 7);
+        assert!(scanned.windows(2).all(|w| w[0].pos() < w[1].pos()));
+        assert_eq!(
+            reader.block(&dup).unwrap(),
+            &data[sections[0].pos() as usize..][..sections[0].len()]
+        );
+        assert_eq!(reader.duplicates().len(), 1);
+
+        // the last section is truncated.
+        buf.truncate(buf.len() - 1);
+        let rs = CarReaderMmap::from_bytes(Bytes::from(buf));
+        assert!(matches!(
+            rs.err(),
+            Some(CarError::UnexpectedEof { offset }) if offset == scanned[6].section_pos()
+        ));
+    }
+}
@@ -0,0 +1,198 @@
+use std::{collections::HashMap, fs::File, io, path::Path};
+
+use cid::Cid;
+
+use crate::{
+    error::CarError,
+    header::CarHeader,
+    reader::{inline_data, inline_ipld, scan_sections, CarReader, ReaderOptions, ScanEnd},
+    section::{decode_ipld, Section},
+    Ipld,
+};
+
+/// read the exact bytes at the offset, the file cursor is not used.
+#[cfg(unix)]
+fn read_exact_at(file: &File, buf: &mut [u8], offset: u64) -> io::Result<()> {
+    std::os::unix::fs::FileExt::read_exact_at(file, buf, offset)
+}
+
+/// read the exact bytes at the offset, the file cursor is not used.
+#[cfg(windows)]
+fn read_exact_at(file: &File, mut buf: &mut [u8], mut offset: u64) -> io::Result<()> {
+    use std::os::windows::fs::FileExt;
+    while !buf.is_empty() {
+        match file.seek_read(buf, offset) {
+            Ok(0) => return Err(io::ErrorKind::UnexpectedEof.into()),
+            Ok(n) => {
+                buf = &mut buf[n..];
+                offset += n as u64;
+            }
+            Err(e) if e.kind() == io::ErrorKind::Interrupted => {}
+            Err(e) => return Err(e),
+        }
+    }
+    Ok(())
+}
+
+/// the thread-safe reader, both CARv1 and CARv2 are supported.
+/// the sections are scanned when the reader is opened, the blocks are read by the positional
+/// reads through `&self`, so the reader can be shared by the threads without lock.
+/// the reader is only available on unix and windows, which have the positional reads.
+pub struct CarReaderShared {
+    file: File,
+    header: CarHeader,
+    sections: Vec<Section>,
+    index: HashMap<Cid, usize>,
+    opts: ReaderOptions,
+}
+
+impl CarReaderShared {
+    #[inline]
+    pub fn open<P: AsRef<Path>>(path: P) -> Result<Self, CarError> {
+        Self::new(File::open(path)?)
+    }
+
+    #[inline(always)]
+    pub fn new(file: File) -> Result<Self, CarError> {
+        Self::new_with_options(file, &ReaderOptions::default())
+    }
+
+    pub fn new_with_options(file: File, opts: &ReaderOptions) -> Result<Self, CarError> {
+        let mut r = io::BufReader::new(&file);
+        let header = CarHeader::read_header_with(&mut r, opts)?;
+        let scan = scan_sections(&mut r, ScanEnd::of(&header, 0), opts)?.into_result()?;
+        Ok(Self {
+            file,
+            header,
+            sections: scan.sections,
+            index: scan.index,
+            opts: opts.clone(),
+        })
+    }
+
+    #[inline(always)]
+    pub fn header(&self) -> &CarHeader {
+        &self.header
+    }
+
+    /// all the sections in the file order.
+    #[inline(always)]
+    pub fn sections(&self) -> &[Section] {
+        &self.sections
+    }
+
+    fn read_data(&self, s: &Section) -> Result<Vec<u8>, CarError> {
+        let mut buf = vec![0u8; s.len()];
+        read_exact_at(&self.file, &mut buf, s.pos())
+            .map_err(|e| CarError::io_at(e, s.section_pos()))?;
+        Ok(buf)
+    }
+
+    /// read the section data by the positional read.
+    pub fn read_section_data(&self, cid: &Cid) -> Result<Vec<u8>, CarError> {
+        let s = match self.index.get(cid) {
+            Some(idx) => &self.sections[*idx],
+            None => return inline_data(cid),
+        };
+        let data = self.read_data(s)?;
+        if self.opts.is_hash_verified() {
+            s.verify(&data)?;
+        }
+        Ok(data)
+    }
+
+    /// decode the section by the positional read, the data is verified.
+    pub fn ipld(&self, cid: &Cid) -> Result<Ipld, CarError> {
+        let s = match self.index.get(cid) {
+            Some(idx) => &self.sections[*idx],
+            None => return inline_ipld(cid),
+        };
+        let data = self.read_data(s)?;
+        s.verify(&data)?;
+        decode_ipld(*cid, data)
+    }
+}
+
+impl CarReader for CarReaderShared {
+    #[inline(always)]
+    fn header(&self) -> &CarHeader {
+        &self.header
+    }
+
+    #[inline]
+    fn section(&self, cid: &Cid) -> Result<Option<Section>, CarError> {
+        Ok(self.index.get(cid).map(|idx| self.sections[*idx].clone()))
+    }
+
+    #[inline(always)]
+    fn sections(&self) -> Vec<Section> {
+        self.sections.clone()
+    }
+
+    #[inline(always)]
+    fn read_section_data(&mut self, cid: &Cid) -> Result<Vec<u8>, CarError> {
+        CarReaderShared::read_section_data(self, cid)
+    }
+
+    #[inline(always)]
+    fn ipld(&mut self, cid: &Cid) -> Result<Ipld, CarError> {
+        CarReaderShared::ipld(self, cid)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::reader;
+    use std::{sync::Arc, thread};
+
+    #[test]
+    fn test_shared_read() {
+        fn assert_send_sync<T: Send + Sync>() {}
+        assert_send_sync::<CarReaderShared>();
+
+        let file = std::path::Path::new("test");
+        let file = file.join("carv1-basic.car");
+        let shared = Arc::new(CarReaderShared::open(&file).unwrap());
+        let mut v1 = reader::new_v1(File::open(&file).unwrap()).unwrap();
+        let expected: Vec<_> = v1
+            .sections()
+            .iter()
+            .map(|s| (s.cid(), v1.read_section_data(&s.cid()).unwrap()))
+            .collect();
+        let expected = Arc::new(expected);
+        let handles: Vec<_> = (0..4)
+            .map(|_| {
+                let shared = shared.clone();
+                let expected = expected.clone();
+                thread::spawn(move || {
+                    for (cid, data) in expected.iter() {
+                        assert_eq!(&shared.read_section_data(cid).unwrap(), data);
+                        assert!(shared.ipld(cid).is_ok());
+                    }
+                })
+            })
+            .collect();
+        for h in handles {
+            h.join().unwrap();
+        }
+    }
+
+    #[test]
+    fn test_shared_truncated() {
+        let mut data = std::fs::read("test/carv1-basic.car").unwrap();
+        let last = reader::new_v1(io::Cursor::new(&data)).unwrap().sections()[5].section_pos();
+        data.truncate(data.len() - 1);
+        let path = std::env::temp_dir().join(format!(
+            "blockless-car-{}-test_shared_truncated.car",
+            std::process::id()
+        ));
+        std::fs::write(&path, &data).unwrap();
+        let rs = CarReaderShared::open(&path);
+        std::fs::remove_file(&path).unwrap();
+        assert!(matches!(
+            rs.err(),
+            Some(CarError::UnexpectedEof { offset }) if offset == last
+        ));
+    }
+}
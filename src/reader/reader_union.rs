@@ -0,0 +1,177 @@
+use std::{collections::HashMap, fs::File, io::Seek, path::Path};
+
+use cid::Cid;
+
+use crate::{
+    error::CarError,
+    header::CarHeader,
+    reader::{self, inline_data, inline_ipld, CarReader},
+    section::Section,
+    Ipld,
+};
+
+/// the union reader over the CAR shards, the section indexes of the shards are merged.
+/// the cid is resolved by the first shard which contains it.
+/// the roots are the union of the shard roots, `pick_roots` selects the specific ones.
+pub struct CarReaderUnion {
+    shards: Vec<Box<dyn CarReader>>,
+    /// the cid to the shard index.
+    index: HashMap<Cid, usize>,
+    header: CarHeader,
+}
+
+impl Default for CarReaderUnion {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl CarReaderUnion {
+    pub fn new() -> Self {
+        Self {
+            shards: Vec::new(),
+            index: HashMap::new(),
+            header: CarHeader::new_v1(Vec::new()),
+        }
+    }
+
+    /// open the CAR files, both CARv1 and CARv2 are supported.
+    pub fn open<P: AsRef<Path>>(paths: impl IntoIterator<Item = P>) -> Result<Self, CarError> {
+        let mut union = Self::new();
+        for path in paths {
+            let mut file = File::open(path)?;
+            let version = CarHeader::read_header(&mut file)?.version();
+            file.rewind()?;
+            match version {
+                1 => union.push(reader::new_v1(file)?),
+                _ => union.push(reader::new_v2(file)?),
+            }
+        }
+        Ok(union)
+    }
+
+    /// add the shard, the sections of the shard are merged into the index.
+    pub fn push(&mut self, shard: impl CarReader + 'static) {
+        let idx = self.shards.len();
+        for s in shard.sections() {
+            self.index.entry(s.cid()).or_insert(idx);
+        }
+        let mut roots = self.header.roots();
+        for root in shard.header().roots() {
+            if !roots.contains(&root) {
+                roots.push(root);
+            }
+        }
+        self.header = CarHeader::new_v1(roots);
+        self.shards.push(Box::new(shard));
+    }
+
+    /// select the roots from the union roots.
+    pub fn pick_roots(&mut self, roots: Vec<Cid>) -> Result<(), CarError> {
+        let union_roots = self.header.roots();
+        if let Some(root) = roots.iter().find(|r| !union_roots.contains(r)) {
+            return Err(CarError::NotFound(format!(
+                "root {root} is not in the shards"
+            )));
+        }
+        self.header = CarHeader::new_v1(roots);
+        Ok(())
+    }
+
+    /// the count of the shards.
+    #[inline(always)]
+    pub fn len(&self) -> usize {
+        self.shards.len()
+    }
+
+    #[inline(always)]
+    pub fn is_empty(&self) -> bool {
+        self.shards.is_empty()
+    }
+}
+
+impl CarReader for CarReaderUnion {
+    #[inline(always)]
+    fn header(&self) -> &CarHeader {
+        &self.header
+    }
+
+    fn section(&self, cid: &Cid) -> Result<Option<Section>, CarError> {
+        match self.index.get(cid) {
+            Some(idx) => self.shards[*idx].section(cid),
+            None => Ok(None),
+        }
+    }
+
+    /// the sections of all the shards, in the order of the shards.
+    /// the positions of the sections are relative to their own shard.
+    fn sections(&self) -> Vec<Section> {
+        self.shards.iter().flat_map(|s| s.sections()).collect()
+    }
+
+    fn read_section_data(&mut self, cid: &Cid) -> Result<Vec<u8>, CarError> {
+        match self.index.get(cid) {
+            Some(idx) => self.shards[*idx].read_section_data(cid),
+            None => inline_data(cid),
+        }
+    }
+
+    fn ipld(&mut self, cid: &Cid) -> Result<Ipld, CarError> {
+        match self.index.get(cid) {
+            Some(idx) => self.shards[*idx].ipld(cid),
+            None => inline_ipld(cid),
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::utils::list_call;
+    use crate::writer::{CarWriter, CarWriterV1};
+    use std::{cell::RefCell, io::Cursor};
+
+    #[test]
+    fn test_union_read() {
+        let file = std::path::Path::new("test");
+        let file = file.join("carv1-basic.car");
+        let data = std::fs::read(file).unwrap();
+        let mut full = reader::new_v1(Cursor::new(&data)).unwrap();
+        let root = full.header().roots()[0];
+        let sections = full.sections();
+        let mut union = CarReaderUnion::new();
+        for shard in sections.chunks(3) {
+            let mut buf = Vec::new();
+            let header = CarHeader::new_v1(vec![shard[0].cid()]);
+            let mut writer = CarWriterV1::new(Cursor::new(&mut buf), header);
+            for s in shard {
+                let data = full.read_section_data(&s.cid()).unwrap();
+                writer.write(s.cid(), data).unwrap();
+            }
+            writer.flush().unwrap();
+            union.push(reader::new_v1(Cursor::new(buf)).unwrap());
+        }
+        assert_eq!(union.len(), 2);
+        assert_eq!(union.sections().len(), 6);
+        assert_eq!(union.header().roots().len(), 2);
+        for s in sections.iter() {
+            assert_eq!(union.ipld(&s.cid()).unwrap(), full.ipld(&s.cid()).unwrap());
+        }
+        assert_eq!(
+            union.search_file_cid("not-distributed.jpg").unwrap(),
+            full.search_file_cid("not-distributed.jpg").unwrap()
+        );
+
+        union.pick_roots(vec![root]).unwrap();
+        assert_eq!(union.header().roots(), vec![root]);
+        let names = RefCell::new(Vec::new());
+        list_call(&mut union, |_, n| names.borrow_mut().push(n.to_string())).unwrap();
+        let full_names = RefCell::new(Vec::new());
+        list_call(&mut full, |_, n| {
+            full_names.borrow_mut().push(n.to_string())
+        })
+        .unwrap();
+        assert_eq!(names, full_names);
+        assert!(union.pick_roots(vec![crate::utils::raw_cid(b"")]).is_err());
+    }
+}
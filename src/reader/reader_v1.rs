@@ -1,34 +1,64 @@
 #![allow(unused)]
 use cid::Cid;
 
-use crate::{error::CarError, header::CarHeader, reader::CarReader, section::Section, Ipld};
+use crate::{
+    error::CarError,
+    header::CarHeader,
+    reader::{CarReader, ReaderOptions},
+    section::Section,
+    Ipld,
+};
 use std::{
     collections::HashMap,
     io::{Read, Seek},
 };
 
-use super::read_section;
+use super::{inline_data, inline_ipld, scan_sections, Damage, ScanEnd};
 
+/// the CARv1 reader, the sections are kept in the file order, the duplicated cids are kept.
+/// the lookup index points to the first section of the cid.
 pub(crate) struct CarReaderV1<R> {
     inner: R,
-    sections: HashMap<Cid, Section>,
+    sections: Vec<Section>,
+    index: HashMap<Cid, usize>,
     header: CarHeader,
+    opts: ReaderOptions,
+    /// the damage found in the recovery mode.
+    damage: Option<Damage>,
 }
 
 impl<R> CarReaderV1<R>
 where
     R: Read + Seek,
 {
-    pub(crate) fn new(mut inner: R) -> Result<Self, CarError> {
-        let header = CarHeader::read_header(&mut inner)?;
-        let mut sections = HashMap::new();
-        while let Some(section) = read_section(&mut inner)? {
-            sections.insert(section.cid(), section);
+    #[inline(always)]
+    pub(crate) fn new(inner: R) -> Result<Self, CarError> {
+        Self::new_with_options(inner, &ReaderOptions::default())
+    }
+
+    pub(crate) fn new_with_options(mut inner: R, opts: &ReaderOptions) -> Result<Self, CarError> {
+        let header = CarHeader::read_header_with(&mut inner, opts)?;
+        if let CarHeader::V2(_) = header {
+            return Err(CarError::InvalidFile(
+                "the CARv2 file should be read by the CARv2 reader".into(),
+            ));
         }
+        let mut scan = scan_sections(&mut inner, ScanEnd::of(&header, 0), opts)?;
+        let damage = match scan.error.take() {
+            Some(e) if opts.is_recovery_enabled() => Some(Damage {
+                offset: scan.end,
+                reason: e.to_string(),
+            }),
+            Some(e) => return Err(e),
+            None => None,
+        };
         Ok(Self {
             inner,
             header,
-            sections,
+            sections: scan.sections,
+            index: scan.index,
+            opts: opts.clone(),
+            damage,
         })
     }
 }
@@ -42,27 +72,43 @@ where
         &self.header
     }
 
+    #[inline]
+    fn section(&self, cid: &Cid) -> Result<Option<Section>, CarError> {
+        Ok(self.index.get(cid).map(|idx| self.sections[*idx].clone()))
+    }
+
+    /// all the sections in the file order, include the duplicated ones.
     #[inline(always)]
     fn sections(&self) -> Vec<Section> {
-        self.sections.values().map(Section::clone).collect()
+        self.sections.clone()
+    }
+
+    #[inline(always)]
+    fn damage(&self) -> Option<&Damage> {
+        self.damage.as_ref()
     }
 
     #[inline]
     fn read_section_data(&mut self, cid: &Cid) -> Result<Vec<u8>, CarError> {
-        let s = self
-            .sections
-            .get(cid)
-            .ok_or(CarError::InvalidSection("cid not exist".into()))?;
-        s.read_data(&mut self.inner)
+        let idx = match self.index.get(cid) {
+            Some(idx) => *idx,
+            None => return inline_data(cid),
+        };
+        let s = &self.sections[idx];
+        let data = s.read_data(&mut self.inner)?;
+        if self.opts.is_hash_verified() {
+            s.verify(&data)?;
+        }
+        Ok(data)
     }
 
     #[inline]
     fn ipld(&mut self, cid: &Cid) -> Result<Ipld, CarError> {
-        let s = self
-            .sections
-            .get_mut(cid)
-            .ok_or(CarError::NotFound("cid not exist".into()))?;
-        s.ipld(&mut self.inner)
+        let idx = match self.index.get(cid) {
+            Some(idx) => *idx,
+            None => return inline_ipld(cid),
+        };
+        self.sections[idx].ipld(&mut self.inner)
     }
 }
 
@@ -91,4 +137,33 @@ mod test {
         let rs = reader.search_file_cid("not-distributed.jpg");
         println!("{rs:?}");
     }
+
+    #[test]
+    fn test_read_order_and_duplicates() {
+        use crate::writer::{CarWriter, CarWriterV1};
+        let cids: Vec<_> = (0..3u8).map(|i| crate::utils::raw_cid(&[i])).collect();
+        let mut buf = Vec::new();
+        let mut writer = CarWriterV1::new(
+            std::io::Cursor::new(&mut buf),
+            CarHeader::new_v1(vec![cids[0]]),
+        );
+        for i in [2, 0, 1, 0, 2, 0] {
+            writer.write(cids[i], [i as u8]).unwrap();
+        }
+        writer.flush().unwrap();
+        let mut reader = CarReaderV1::new(std::io::Cursor::new(&buf)).unwrap();
+        let sections = reader.sections();
+        let order: Vec<_> = sections.iter().map(|s| s.cid()).collect();
+        let expected: Vec<_> = [2, 0, 1, 0, 2, 0].iter().map(|i| cids[*i]).collect();
+        assert_eq!(order, expected);
+        let pos: Vec<_> = sections.iter().map(|s| s.section_pos()).collect();
+        assert_eq!(
+            reader.duplicates(),
+            vec![
+                (cids[2], vec![pos[0], pos[4]]),
+                (cids[0], vec![pos[1], pos[3], pos[5]]),
+            ]
+        );
+        assert_eq!(reader.read_section_data(&cids[1]).unwrap(), vec![1]);
+    }
 }
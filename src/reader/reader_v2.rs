@@ -0,0 +1,252 @@
+use cid::Cid;
+
+use crate::{
+    error::CarError,
+    header::CarHeader,
+    index::{read_index, Index, IndexCodec},
+    reader::{CarReader, Damage, ReaderOptions},
+    section::Section,
+    Ipld,
+};
+use std::{
+    cell::{OnceCell, RefCell},
+    io::{Read, Seek, SeekFrom},
+};
+
+use super::{inline_data, inline_ipld, read_section_with, scan_sections, ScanEnd};
+
+/// the CARv2 reader, the sections are resolved by the index on demand.
+/// if the file has no index, the index will be generated by scanning the data payload.
+/// the CARv1 file with the detached index is also read by this reader, the data offset is 0.
+pub(crate) struct CarReaderV2<R> {
+    inner: RefCell<R>,
+    header: CarHeader,
+    index: Index,
+    data_offset: u64,
+    /// the sections resolved from the index, they are resolved once by `sections`.
+    sections: OnceCell<Vec<Section>>,
+    /// the first index entry which can't be resolved to the section.
+    damage: OnceCell<Damage>,
+    opts: ReaderOptions,
+}
+
+impl<R> CarReaderV2<R>
+where
+    R: Read + Seek,
+{
+    #[inline(always)]
+    pub(crate) fn new(inner: R) -> Result<Self, CarError> {
+        Self::new_with_options(inner, &ReaderOptions::default())
+    }
+
+    pub(crate) fn new_with_options(mut inner: R, opts: &ReaderOptions) -> Result<Self, CarError> {
+        let mut header = CarHeader::read_header_with(&mut inner, opts)?;
+        let end = ScanEnd::of(&header, 0);
+        let v2 = match header {
+            CarHeader::V2(ref mut v2) => v2,
+            CarHeader::V1(_) => return Err(CarError::InvalidFile("Not the CARv2 file".into())),
+        };
+        let sections = OnceCell::new();
+        let index = if v2.has_index() {
+            inner.seek(SeekFrom::Start(v2.index_offset))?;
+            let index = read_index(&mut inner)?;
+            v2.index_codec = Some(index.codec());
+            index
+        } else {
+            // the data size of the unfinalized file is 0, the payload is scanned to the EOF.
+            let scanned = scan_sections(&mut inner, end, opts)?
+                .into_result()?
+                .sections;
+            let codec = v2.index_codec.unwrap_or(IndexCodec::MultihashIndexSorted);
+            let index = Index::from_sections(codec, scanned.iter(), v2.data_offset);
+            let _ = sections.set(scanned);
+            index
+        };
+        opts.check_sections(index.len())?;
+        let data_offset = v2.data_offset;
+        Ok(Self {
+            inner: RefCell::new(inner),
+            header,
+            index,
+            data_offset,
+            sections,
+            damage: OnceCell::new(),
+            opts: opts.clone(),
+        })
+    }
+
+    /// open the CARv1 file with the detached index, the sections are not scanned.
+    pub(crate) fn with_index(mut inner: R, index: Index) -> Result<Self, CarError> {
+        let header = CarHeader::read_header(&mut inner)?;
+        if let CarHeader::V2(_) = header {
+            return Err(CarError::InvalidFile(
+                "the detached index should be used with the CARv1 file".into(),
+            ));
+        }
+        Ok(Self {
+            inner: RefCell::new(inner),
+            header,
+            index,
+            data_offset: 0,
+            sections: OnceCell::new(),
+            damage: OnceCell::new(),
+            opts: ReaderOptions::default(),
+        })
+    }
+
+    /// resolve the sections of the index in the file order, the first failure is recorded
+    /// as the damage and the section is skipped.
+    fn resolve_sections(&self) -> Vec<Section> {
+        let mut offsets: Vec<u64> = self.index.offsets().collect();
+        offsets.sort_unstable();
+        offsets.dedup();
+        let mut inner = self.inner.borrow_mut();
+        let mut sections = Vec::with_capacity(offsets.len());
+        for offset in offsets {
+            let pos = self.data_offset + offset;
+            match Self::read_section_at(&mut *inner, pos, &self.opts) {
+                Ok(s) => sections.push(s),
+                Err(e) => {
+                    let _ = self.damage.set(Damage {
+                        offset: pos,
+                        reason: e.to_string(),
+                    });
+                }
+            }
+        }
+        sections
+    }
+
+    /// read the section at the offset relative to the data payload.
+    fn read_section_at<T>(mut inner: T, pos: u64, opts: &ReaderOptions) -> Result<Section, CarError>
+    where
+        T: Read + Seek,
+    {
+        inner.seek(SeekFrom::Start(pos))?;
+        read_section_with(&mut inner, opts)?.ok_or(CarError::UnexpectedEof { offset: pos })
+    }
+
+    fn lookup(&self, cid: &Cid) -> Result<Option<Section>, CarError> {
+        let offset = match self.index.get(cid) {
+            Some(o) => o,
+            None => return Ok(None),
+        };
+        let pos = self.data_offset + offset;
+        let s = Self::read_section_at(&mut *self.inner.borrow_mut(), pos, &self.opts)?;
+        if s.cid().hash() != cid.hash() {
+            return Err(CarError::InvalidSection(format!(
+                "the index of {cid} points to the section of {}",
+                s.cid()
+            )));
+        }
+        // the index is keyed by the multihash, the codec of the cid may be different.
+        Ok(Some(Section::new(*cid, s.pos(), s.len())))
+    }
+}
+
+impl<R> CarReader for CarReaderV2<R>
+where
+    R: Read + Seek,
+{
+    #[inline(always)]
+    fn header(&self) -> &CarHeader {
+        &self.header
+    }
+
+    #[inline(always)]
+    fn section(&self, cid: &Cid) -> Result<Option<Section>, CarError> {
+        self.lookup(cid)
+    }
+
+    /// all the sections in the index, the section which can't be resolved is skipped
+    /// and reported by `damage`.
+    fn sections(&self) -> Vec<Section> {
+        self.sections
+            .get_or_init(|| self.resolve_sections())
+            .clone()
+    }
+
+    #[inline(always)]
+    fn damage(&self) -> Option<&Damage> {
+        self.damage.get()
+    }
+
+    #[inline]
+    fn read_section_data(&mut self, cid: &Cid) -> Result<Vec<u8>, CarError> {
+        let s = match self.lookup(cid)? {
+            Some(s) => s,
+            None => return inline_data(cid),
+        };
+        let data = s.read_data(self.inner.get_mut())?;
+        if self.opts.is_hash_verified() {
+            s.verify(&data)?;
+        }
+        Ok(data)
+    }
+
+    #[inline]
+    fn ipld(&mut self, cid: &Cid) -> Result<Ipld, CarError> {
+        let mut s = match self.lookup(cid)? {
+            Some(s) => s,
+            None => return inline_ipld(cid),
+        };
+        s.ipld(self.inner.get_mut())
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::header::{CarHeaderV1, CarHeaderV2};
+    use crate::utils::wrap_v1;
+    use std::io::Cursor;
+
+    #[test]
+    fn test_read_without_index() {
+        let file = std::path::Path::new("test");
+        let file = file.join("carv1-basic.car");
+        let payload = std::fs::read(file).unwrap();
+        let mut header = CarHeaderV2::new(CarHeaderV1::default());
+        header.data_size = payload.len() as u64;
+        let mut buf = header.encode();
+        buf.extend_from_slice(&payload);
+        let mut reader = CarReaderV2::new(Cursor::new(buf)).unwrap();
+        let roots = reader.header().roots();
+        assert_eq!(reader.sections().len(), 6);
+        for r in roots.iter() {
+            let s_ipld = reader.ipld(r).unwrap();
+            assert!(matches!(s_ipld, Ipld::Map(_)));
+        }
+        let cid = reader.search_file_cid("not-distributed.jpg").unwrap();
+        assert!(reader.read_section_data(&cid).is_ok());
+
+        // the unfinalized file, the data size is 0.
+        let mut buf = CarHeaderV2::new(CarHeaderV1::default()).encode();
+        buf.extend_from_slice(&payload);
+        let reader = CarReaderV2::new(Cursor::new(buf)).unwrap();
+        assert_eq!(reader.sections().len(), 6);
+    }
+
+    #[test]
+    fn test_unresolved_section() {
+        let payload = std::fs::read("test/carv1-basic.car").unwrap();
+        let mut buf = Vec::new();
+        wrap_v1(
+            Cursor::new(&payload),
+            &mut buf,
+            IndexCodec::MultihashIndexSorted,
+        )
+        .unwrap();
+        let reader = CarReaderV2::new(Cursor::new(&buf)).unwrap();
+        let sections = reader.sections();
+        assert!(reader.damage().is_none());
+        // the index entry of the last section points to the garbage.
+        let last = sections.last().unwrap().section_pos();
+        drop(reader);
+        buf[last as usize] = 0;
+        let reader = CarReaderV2::new(Cursor::new(&buf)).unwrap();
+        assert_eq!(reader.sections().len(), 5);
+        assert_eq!(reader.damage().unwrap().offset, last);
+        assert_eq!(reader.sections().len(), 5);
+    }
+}
@@ -1,11 +1,47 @@
 #![allow(unused)]
 use std::io::{Read, Seek, SeekFrom};
 
-use cid::Cid;
-use ipld::Block;
+use cid::{
+    multihash::{Code, MultihashDigest},
+    Cid,
+};
+use integer_encoding::VarInt;
+use ipld::{Block, IpldCodec};
 
 use crate::{error::CarError, Ipld};
 
+const IDENTITY_CODE: u64 = 0x00;
+
+/// the data inlined in the cid with the identity multihash, none for the other cids.
+#[inline]
+pub fn identity_data(cid: &Cid) -> Option<&[u8]> {
+    let mh = cid.hash();
+    (mh.code() == IDENTITY_CODE).then(|| mh.digest())
+}
+
+/// decode the block data by the codec of the cid, the data is not verified.
+pub(crate) fn decode_ipld(cid: Cid, data: Vec<u8>) -> Result<Ipld, CarError> {
+    if IpldCodec::try_from(cid.codec()).is_err() {
+        return Err(CarError::UnsupportedCodec {
+            cid,
+            codec: cid.codec(),
+        });
+    }
+    let block = Block::<ipld::DefaultParams>::new_unchecked(cid, data);
+    block.ipld().map_err(|e| CarError::Parsing(e.to_string()))
+}
+
+/// check the data against the multihash of the cid.
+/// identity and the multihashes of the `Code` table are supported.
+pub fn verify_hash(cid: &Cid, data: &[u8]) -> Result<bool, CarError> {
+    let mh = cid.hash();
+    if mh.code() == IDENTITY_CODE {
+        return Ok(mh.digest() == data);
+    }
+    let code = Code::try_from(mh.code()).map_err(|_| CarError::UnsupportedHash(mh.code()))?;
+    Ok(code.digest(data).digest() == mh.digest())
+}
+
 #[derive(Debug, Clone)]
 pub struct Section {
     cid: Cid,
@@ -25,7 +61,9 @@ impl Section {
     {
         seeker.seek(SeekFrom::Start(self.pos))?;
         let mut buf = vec![0u8; self.len];
-        seeker.read_exact(&mut buf)?;
+        seeker
+            .read_exact(&mut buf)
+            .map_err(|e| CarError::io_at(e, self.section_pos()))?;
         Ok(buf)
     }
 
@@ -35,8 +73,21 @@ impl Section {
         T: Seek + Read,
     {
         let data = self.read_data(&mut seeker)?;
-        let block = Block::<ipld::DefaultParams>::new(self.cid, data).unwrap();
-        block.ipld().map_err(|e| CarError::Parsing(e.to_string()))
+        self.verify(&data)?;
+        decode_ipld(self.cid, data)
+    }
+
+    /// verify the section data by the cid,
+    /// the offset of the mismatch error is the start of the section.
+    #[inline]
+    pub fn verify(&self, data: &[u8]) -> Result<(), CarError> {
+        if !verify_hash(&self.cid, data)? {
+            return Err(CarError::HashMismatch {
+                cid: self.cid,
+                offset: self.section_pos(),
+            });
+        }
+        Ok(())
     }
 
     #[inline(always)]
@@ -49,9 +100,83 @@ impl Section {
         self.pos
     }
 
+    /// the position of the section start, include the varint length and the cid.
+    #[inline]
+    pub fn section_pos(&self) -> u64 {
+        let cid_len = self.cid.to_bytes().len();
+        let varint_len = (cid_len + self.len).required_space();
+        self.pos - (cid_len + varint_len) as u64
+    }
+
     #[allow(clippy::len_without_is_empty)]
     #[inline(always)]
     pub fn len(&self) -> usize {
         self.len
     }
 }
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::header::CarHeader;
+    use crate::reader::{self, CarReader, ReaderOptions};
+    use crate::writer::{CarWriter, CarWriterV1};
+    use ipld::raw::RawCodec;
+    use std::io::Cursor;
+
+    #[test]
+    fn test_verify_hash() {
+        let data = b"test";
+        for code in [
+            Code::Sha2_256,
+            Code::Sha2_512,
+            Code::Sha3_256,
+            Code::Blake2b256,
+            Code::Blake3_256,
+        ] {
+            let cid = Cid::new_v1(RawCodec.into(), code.digest(data));
+            assert!(verify_hash(&cid, data).unwrap());
+            assert!(!verify_hash(&cid, b"test2").unwrap());
+        }
+        let identity = cid::multihash::Multihash::wrap(IDENTITY_CODE, data).unwrap();
+        let cid = Cid::new_v1(RawCodec.into(), identity);
+        assert!(verify_hash(&cid, data).unwrap());
+        assert!(!verify_hash(&cid, b"test2").unwrap());
+        let unknown = cid::multihash::Multihash::wrap(0x300000, data).unwrap();
+        let cid = Cid::new_v1(RawCodec.into(), unknown);
+        assert!(matches!(
+            verify_hash(&cid, data),
+            Err(CarError::UnsupportedHash(0x300000))
+        ));
+    }
+
+    #[test]
+    fn test_hash_mismatch() {
+        let cid = Cid::new_v1(RawCodec.into(), Code::Sha2_256.digest(b"test"));
+        let mut buf = Vec::new();
+        let mut writer = CarWriterV1::new(Cursor::new(&mut buf), CarHeader::new_v1(vec![cid]));
+        writer.write(cid, b"test").unwrap();
+        writer.flush().unwrap();
+        let last = buf.len() - 1;
+        buf[last] = b'x';
+
+        let mut r = reader::new_v1(Cursor::new(&buf)).unwrap();
+        let offset = r.sections()[0].section_pos();
+        assert_eq!(r.read_section_data(&cid).unwrap(), b"tesx");
+        assert!(matches!(
+            r.ipld(&cid),
+            Err(CarError::HashMismatch { cid: c, offset: o }) if c == cid && o == offset
+        ));
+        // the utilities return the mismatch instead of panicking.
+        assert!(matches!(
+            crate::utils::ipld_write(&mut r, cid, &mut Vec::new()),
+            Err(CarError::HashMismatch { .. })
+        ));
+        let opts = ReaderOptions::default().verify_hashes(true);
+        let mut r = reader::new_v1_with_options(Cursor::new(&buf), &opts).unwrap();
+        assert!(matches!(
+            r.read_section_data(&cid),
+            Err(CarError::HashMismatch { .. })
+        ));
+    }
+}
@@ -1,11 +1,19 @@
 mod archive_local;
 mod cat_file;
+mod convert;
+mod detached_index;
 mod extract;
 mod ls;
+mod recover;
+mod stats;
 
 pub use archive_local::*;
 pub use cat_file::*;
+pub use convert::*;
+pub use detached_index::*;
 pub use extract::*;
 pub use ls::*;
+pub use recover::*;
+pub use stats::*;
 
 pub(crate) const BLAKE2B256_CODEC: u64 = 0xb220;
\ No newline at end of file
@@ -9,9 +9,10 @@ use std::{
 use crate::{
     codec::Encoder,
     error::CarError,
-    header::CarHeaderV1,
+    header::{CarHeaderV1, CarHeaderV2},
+    index::IndexCodec,
     unixfs::{FileType, Link, UnixFs},
-    writer::{CarWriter, CarWriterV1, WriteStream},
+    writer::{CarWriter, CarWriterV1, CarWriterV2, WriteStream},
     CarHeader, Ipld,
 };
 use cid::{
@@ -88,12 +89,72 @@ fn cid_gen() -> impl FnMut(WriteStream) -> Option<Result<Cid, CarError>> {
     };
 }
 
+/// the options of `archive_local_with_options`, the CARv1 file is written by default.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct ArchiveOptions {
+    /// the index codec of the CARv2 file, none for the CARv1 file.
+    v2_index: Option<IndexCodec>,
+}
+
+impl ArchiveOptions {
+    /// write the CARv2 file, the index of the codec is appended to the file.
+    #[inline]
+    pub fn v2(mut self, codec: IndexCodec) -> Self {
+        self.v2_index = Some(codec);
+        self
+    }
+
+    #[inline(always)]
+    pub fn version(&self) -> u64 {
+        match self.v2_index {
+            Some(_) => 2,
+            None => 1,
+        }
+    }
+}
+
 /// archive the directory to the target CAR format file
 /// `path` is the directory archived in to the CAR file.
 /// `to_carfile` is the target file.
+#[inline]
 pub fn archive_local<T>(path: impl AsRef<Path>, to_carfile: T) -> Result<(), CarError>
 where
     T: std::io::Write + std::io::Seek,
+{
+    archive_local_with_options(path, to_carfile, ArchiveOptions::default())
+}
+
+/// archive the directory to the target CAR format file, the version is chosen by the options.
+/// `path` is the directory archived in to the CAR file.
+/// `to_carfile` is the target file.
+pub fn archive_local_with_options<T>(
+    path: impl AsRef<Path>,
+    to_carfile: T,
+    opts: ArchiveOptions,
+) -> Result<(), CarError>
+where
+    T: std::io::Write + std::io::Seek,
+{
+    // ensure sufficient file block size for head, after the root cid generated using the content, fill back the head.
+    let roots = vec![empty_pb_cid()];
+    match opts.v2_index {
+        Some(codec) => {
+            let mut header = CarHeaderV2::new(CarHeaderV1::new(roots));
+            header.index_codec = Some(codec);
+            let writer = CarWriterV2::new(to_carfile, CarHeader::V2(header))?;
+            archive_by_writer(path, writer)
+        }
+        None => {
+            let header = CarHeader::new_v1(roots);
+            archive_by_writer(path, CarWriterV1::new(to_carfile, header))
+        }
+    }
+}
+
+/// archive the directory by the writer.
+fn archive_by_writer<W>(path: impl AsRef<Path>, mut writer: W) -> Result<(), CarError>
+where
+    W: CarWriter,
 {
     let src_path = path.as_ref();
     if !src_path.exists() {
@@ -101,10 +162,7 @@ where
     }
     let root_path = src_path.absolutize().unwrap();
     let path = root_path.to_path_buf();
-    // ensure sufficient file block size for head, after the root cid generated using the content, fill back the head.
-    let mut root_cid = Some(empty_pb_cid());
-    let header = CarHeader::new_v1(vec![root_cid.unwrap()]);
-    let mut writer = CarWriterV1::new(to_carfile, header);
+    let mut root_cid = None;
     walk_dir(
         path,
         |(abs_path, parent_idx), path_map| -> Result<(), CarError> {
@@ -176,7 +234,8 @@ where
     )?;
     let root_cid = root_cid.ok_or(CarError::NotFound("root cid not found.".to_string()))?;
     let header = CarHeader::V1(CarHeaderV1::new(vec![root_cid]));
-    writer.rewrite_header(header)
+    writer.rewrite_header(header)?;
+    writer.finalize()
 }
 
 pub fn pipe_raw_cid<R, W>(r: &mut R, w: &mut W) -> Result<Cid, CarError> 
@@ -273,3 +332,30 @@ where
     }
     Ok(())
 }
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::reader::{self, CarReader};
+    use std::io::Cursor;
+
+    #[test]
+    fn test_archive_local_v2() {
+        let opts = ArchiveOptions::default().v2(IndexCodec::IndexSorted);
+        assert_eq!(opts.version(), 2);
+        let mut buf = Vec::new();
+        archive_local_with_options("src/index", Cursor::new(&mut buf), opts).unwrap();
+        let mut r = reader::new_v2(Cursor::new(&buf)).unwrap();
+        match r.header() {
+            CarHeader::V2(v2) => assert_eq!(v2.index_codec, Some(IndexCodec::IndexSorted)),
+            CarHeader::V1(_) => panic!("should be the CARv2 header"),
+        }
+        let root = r.header().roots()[0];
+        assert!(r.ipld(&root).is_ok());
+
+        let mut v1 = Vec::new();
+        archive_local("src/index", Cursor::new(&mut v1)).unwrap();
+        let r = reader::new_v1(Cursor::new(&v1)).unwrap();
+        assert_eq!(r.header().roots(), vec![root]);
+    }
+}
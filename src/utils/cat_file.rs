@@ -30,7 +30,7 @@ fn ipld_write_inner(
     output: &mut impl Write,
 ) -> Result<(), CarError> {
     while let Some(file_cid) = vecq.pop_front() {
-        let file_ipld: Ipld = reader.ipld(&file_cid).unwrap();
+        let file_ipld: Ipld = reader.ipld(&file_cid)?;
 
         match file_ipld {
             Ipld::Bytes(b) => {
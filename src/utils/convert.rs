@@ -0,0 +1,115 @@
+use std::io::{self, Read, Seek, SeekFrom, Write};
+
+use crate::{
+    error::CarError,
+    header::{CarHeader, CarHeaderV2},
+    index::{generate_index, write_index, IndexCodec},
+    reader::{scan_sections, ReaderOptions, ScanEnd},
+};
+
+/// wrap the CARv1 stream into the CARv2 container, the index is generated by scanning the sections.
+/// the CARv1 payload is copied byte-for-byte, the blocks are not loaded into memory.
+/// `from_car` is the CARv1 stream.
+/// `to_car` is the target CARv2 stream.
+pub fn wrap_v1<R, W>(mut from_car: R, mut to_car: W, codec: IndexCodec) -> Result<(), CarError>
+where
+    R: Read + Seek,
+    W: Write,
+{
+    let start = from_car.stream_position()?;
+    let index = generate_index(&mut from_car, codec)?;
+    let data_size = from_car.stream_position()? - start;
+    from_car.seek(SeekFrom::Start(start))?;
+    let inner = match CarHeader::read_header(&mut from_car)? {
+        CarHeader::V1(v1) => v1,
+        CarHeader::V2(_) => unreachable!("the index is generated from the CARv1 file"),
+    };
+    let mut header = CarHeaderV2::new(inner);
+    header.data_size = data_size;
+    header.index_offset = header.data_offset + data_size;
+    header.index_codec = Some(codec);
+    header.characteristics.set_fully_indexed(true);
+    to_car.write_all(&header.encode())?;
+    from_car.seek(SeekFrom::Start(start))?;
+    let n = io::copy(&mut (&mut from_car).take(data_size), &mut to_car)?;
+    if n != data_size {
+        return Err(CarError::IO(io::ErrorKind::UnexpectedEof.into()));
+    }
+    write_index(&mut to_car, &index)?;
+    to_car.flush()?;
+    Ok(())
+}
+
+/// extract the inner CARv1 payload from the CARv2 stream byte-for-byte.
+/// the payload of the unfinalized CARv2 file, whose data size is 0, ends at its last section.
+/// `from_car` is the CARv2 stream.
+/// `to_car` is the target CARv1 stream.
+pub fn unwrap_v2<R, W>(mut from_car: R, mut to_car: W) -> Result<(), CarError>
+where
+    R: Read + Seek,
+    W: Write,
+{
+    let start = from_car.stream_position()?;
+    let car_header = CarHeader::read_header(&mut from_car)?;
+    let end = ScanEnd::of(&car_header, start);
+    let header = match car_header {
+        CarHeader::V2(v2) => v2,
+        CarHeader::V1(_) => return Err(CarError::InvalidFile("Not the CARv2 file".into())),
+    };
+    let data_size = match header.data_size {
+        0 => {
+            let opts = ReaderOptions::default();
+            let scan = scan_sections(&mut from_car, end, &opts)?.into_result()?;
+            scan.end - start - header.data_offset
+        }
+        size => size,
+    };
+    from_car.seek(SeekFrom::Start(start + header.data_offset))?;
+    let n = io::copy(&mut from_car.take(data_size), &mut to_car)?;
+    if n != data_size {
+        return Err(CarError::IO(io::ErrorKind::UnexpectedEof.into()));
+    }
+    to_car.flush()?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod test {
+    use std::io::Cursor;
+
+    use super::*;
+    use crate::reader::{CarReader, CarReaderV2};
+
+    #[test]
+    fn test_wrap_unwrap() {
+        let file = std::path::Path::new("test");
+        let file = file.join("carv1-basic.car");
+        let v1 = std::fs::read(file).unwrap();
+        let mut v2 = Vec::new();
+        wrap_v1(Cursor::new(&v1), &mut v2, IndexCodec::MultihashIndexSorted).unwrap();
+        let mut reader = CarReaderV2::new(Cursor::new(&v2)).unwrap();
+        assert_eq!(reader.sections().len(), 6);
+        let cid = reader.search_file_cid("not-distributed.jpg").unwrap();
+        assert!(reader.read_section_data(&cid).is_ok());
+        let mut unwrapped = Vec::new();
+        unwrap_v2(Cursor::new(&v2), &mut unwrapped).unwrap();
+        assert_eq!(unwrapped, v1);
+    }
+
+    #[test]
+    fn test_unwrap_unfinalized() {
+        let v1 = std::fs::read("test/carv1-basic.car").unwrap();
+        let mut v2 = CarHeaderV2::new(Default::default()).encode();
+        v2.extend_from_slice(&v1);
+        // the zeros left by the resumed blockstore.
+        v2.extend_from_slice(&[0u8; 16]);
+        let mut unwrapped = Vec::new();
+        unwrap_v2(Cursor::new(&v2), &mut unwrapped).unwrap();
+        assert_eq!(unwrapped, v1);
+
+        // the last section is truncated.
+        v2.truncate(v2.len() - 20);
+        let rs = unwrap_v2(Cursor::new(&v2), &mut Vec::new());
+        assert!(rs.is_err());
+    }
+}
@@ -0,0 +1,81 @@
+use std::{
+    fs::File,
+    io::{BufReader, BufWriter, Write},
+    path::{Path, PathBuf},
+};
+
+use crate::{
+    error::CarError,
+    index::{generate_index, read_index, write_index, IndexCodec},
+    reader::{new_v1_with_index, CarReader},
+};
+
+/// the detached index file path of the CAR file, e.g. `file.car.idx`.
+pub fn detached_index_path(car_path: impl AsRef<Path>) -> PathBuf {
+    let mut path = car_path.as_ref().as_os_str().to_owned();
+    path.push(".idx");
+    path.into()
+}
+
+/// scan the CARv1 file and write the detached index file next to it.
+/// `car_path` is the CARv1 file, the index file is `car_path` with the `.idx` suffix.
+pub fn write_detached_index(
+    car_path: impl AsRef<Path>,
+    codec: IndexCodec,
+) -> Result<PathBuf, CarError> {
+    let car_path = car_path.as_ref();
+    let file = BufReader::new(File::open(car_path)?);
+    let index = generate_index(file, codec)?;
+    let index_path = detached_index_path(car_path);
+    let mut file = BufWriter::new(File::create(&index_path)?);
+    write_index(&mut file, &index)?;
+    file.flush()?;
+    Ok(index_path)
+}
+
+/// open the CARv1 file with the detached index file next to it, the sections are not scanned.
+pub fn open_with_detached_index(car_path: impl AsRef<Path>) -> Result<impl CarReader, CarError> {
+    let car_path = car_path.as_ref();
+    let index_file = BufReader::new(File::open(detached_index_path(car_path))?);
+    let index = read_index(index_file)?;
+    new_v1_with_index(File::open(car_path)?, index)
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    /// the temporary directory of the test, it's removed when dropped.
+    struct TempDir(PathBuf);
+
+    impl TempDir {
+        /// the directory is unique to the process and the test.
+        fn new(name: &str) -> Self {
+            let dir =
+                std::env::temp_dir().join(format!("blockless-car-{}-{name}", std::process::id()));
+            std::fs::create_dir_all(&dir).unwrap();
+            Self(dir)
+        }
+    }
+
+    impl Drop for TempDir {
+        fn drop(&mut self) {
+            let _ = std::fs::remove_dir_all(&self.0);
+        }
+    }
+
+    #[test]
+    fn test_detached_index() {
+        let file = std::path::Path::new("test");
+        let file = file.join("carv1-basic.car");
+        let dir = TempDir::new("test_detached_index");
+        let car_path = dir.0.join("detached.car");
+        std::fs::copy(file, &car_path).unwrap();
+        let index_path = write_detached_index(&car_path, IndexCodec::MultihashIndexSorted).unwrap();
+        assert_eq!(index_path, dir.0.join("detached.car.idx"));
+        let mut reader = open_with_detached_index(&car_path).unwrap();
+        assert_eq!(reader.sections().len(), 6);
+        let cid = reader.search_file_cid("not-distributed.jpg").unwrap();
+        assert!(reader.read_section_data(&cid).is_ok());
+    }
+}
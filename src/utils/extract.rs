@@ -86,7 +86,7 @@ fn extract_ipld_inner(
             Some(f) => f,
             None => root_path.clone(),
         };
-        let file_ipld: Ipld = reader.ipld(&cid).unwrap();
+        let file_ipld: Ipld = reader.ipld(&cid)?;
         let file_links = match file_ipld {
             Ipld::Bytes(b) => {
                 let mut file = fs::OpenOptions::new()
@@ -132,7 +132,7 @@ fn extract_ipld_inner(
                     .open(&full_path)
                     .unwrap();
                 for ufs in f.links() {
-                    let file_ipld: Ipld = reader.ipld(&ufs.hash).unwrap();
+                    let file_ipld: Ipld = reader.ipld(&ufs.hash)?;
                     match file_ipld {
                         Ipld::Bytes(b) => {
                             file.write_all(&b).unwrap();
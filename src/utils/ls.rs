@@ -69,4 +69,58 @@ where
         walk(&mut queue, reader, &list_f)?;
     }
     Ok(())
-}
\ No newline at end of file
+}
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::{
+        header::CarHeader,
+        reader,
+        unixfs::Link,
+        utils::{empty_pb_cid, ipld_write},
+        writer::{CarWriter, CarWriterV1},
+    };
+    use cid::multihash::Multihash;
+    use ipld::{pb::DagPbCodec, prelude::Codec};
+    use std::{cell::RefCell, io::Cursor};
+
+    #[test]
+    fn test_identity_cid() {
+        let identity = |codec: u64, data: &[u8]| {
+            Cid::new_v1(codec, Multihash::wrap(0x00, data).unwrap())
+        };
+        let file_cid = identity(RawCodec.into(), b"hello");
+        let sub: Ipld = UnixFs::new_directory().try_into().unwrap();
+        let sub = DagPbCodec.encode(&sub).unwrap();
+        let sub_cid = identity(DagPbCodec.into(), &sub);
+        let mut root = UnixFs::new_directory();
+        root.add_link(Link::new(file_cid, "inline.txt".into(), 5));
+        root.add_link(Link::new(sub_cid, "sub".into(), sub.len() as _));
+
+        let mut buf = Vec::new();
+        let header = CarHeader::new_v1(vec![empty_pb_cid()]);
+        let mut writer = CarWriterV1::new(Cursor::new(&mut buf), header);
+        let root_cid = writer.write_ipld(root.try_into().unwrap()).unwrap();
+        writer.rewrite_header(CarHeader::new_v1(vec![root_cid])).unwrap();
+        writer.flush().unwrap();
+
+        let mut reader = reader::new_v1(Cursor::new(&buf)).unwrap();
+        assert_eq!(reader.read_section_data(&file_cid).unwrap(), b"hello");
+        let names = RefCell::new(Vec::new());
+        list_call(&mut reader, |_, name| {
+            names.borrow_mut().push(name.to_string())
+        })
+        .unwrap();
+        assert_eq!(
+            names.into_inner(),
+            vec![
+                root_cid.to_string(),
+                format!("{root_cid}/inline.txt"),
+                format!("{root_cid}/sub"),
+            ]
+        );
+        let mut output = Vec::new();
+        ipld_write(&mut reader, root_cid, &mut output).unwrap();
+        assert_eq!(output, b"hello");
+    }
+}
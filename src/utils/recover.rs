@@ -0,0 +1,67 @@
+use std::io::{Read, Seek, Write};
+
+use crate::{
+    error::CarError,
+    reader::{CarReader, CarReaderV1, Damage, ReaderOptions},
+    writer::{CarWriter, CarWriterV1},
+};
+
+/// recover the damaged CARv1 file, the complete sections before the first malformed
+/// or truncated one are copied into the new CARv1 file with the same header.
+/// return the damage, none if the file is not damaged.
+/// `from_car` is the damaged CARv1 stream.
+/// `to_car` is the target CARv1 stream.
+pub fn recover_v1<R, W>(from_car: R, to_car: W) -> Result<Option<Damage>, CarError>
+where
+    R: Read + Seek,
+    W: Write + Seek,
+{
+    let opts = ReaderOptions::lenient().recover(true);
+    let mut reader = CarReaderV1::new_with_options(from_car, &opts)?;
+    let header = reader.header().clone();
+    let mut writer = CarWriterV1::new(to_car, header.clone());
+    for s in reader.sections() {
+        let data = reader.read_section_data(&s.cid())?;
+        writer.write(s.cid(), data)?;
+    }
+    // the header is written even if no section is recovered.
+    writer.rewrite_header(header)?;
+    writer.finalize()?;
+    Ok(reader.damage().cloned())
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::reader;
+    use std::io::Cursor;
+
+    #[test]
+    fn test_recover_v1() {
+        let file = std::path::Path::new("test");
+        let file = file.join("carv1-basic.car");
+        let data = std::fs::read(file).unwrap();
+        let sections = reader::new_v1(Cursor::new(&data)).unwrap().sections();
+        let last = sections.last().unwrap();
+        let truncated = &data[..data.len() - last.len() / 2];
+        assert!(reader::new_v1(Cursor::new(truncated)).is_err());
+
+        let opts = ReaderOptions::default().recover(true);
+        let r = reader::new_v1_with_options(Cursor::new(truncated), &opts).unwrap();
+        assert_eq!(r.sections().len(), sections.len() - 1);
+        assert_eq!(r.damage().unwrap().offset, last.section_pos());
+
+        let mut recovered = Vec::new();
+        let damage = recover_v1(Cursor::new(truncated), Cursor::new(&mut recovered)).unwrap();
+        assert_eq!(damage.unwrap().offset, last.section_pos());
+        assert_eq!(&recovered[..], &data[..last.section_pos() as usize]);
+        let r = reader::new_v1(Cursor::new(&recovered)).unwrap();
+        assert!(r.damage().is_none());
+        assert_eq!(r.sections().len(), sections.len() - 1);
+
+        let mut copied = Vec::new();
+        let damage = recover_v1(Cursor::new(&data), Cursor::new(&mut copied)).unwrap();
+        assert!(damage.is_none());
+        assert_eq!(copied, data);
+    }
+}
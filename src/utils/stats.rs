@@ -0,0 +1,275 @@
+use std::{
+    cmp::Reverse,
+    collections::{BTreeMap, HashSet, VecDeque},
+};
+
+use cid::Cid;
+use ipld::{cbor::DagCborCodec, pb::DagPbCodec};
+
+use crate::{error::CarError, reader::CarReader, section::identity_data};
+
+/// the count of the largest blocks reported by `stats`.
+pub const DEFAULT_LARGEST_BLOCKS: usize = 10;
+
+/// the block in the statistics report.
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+pub struct BlockStat {
+    #[cfg_attr(feature = "serde", serde(serialize_with = "ser::cid"))]
+    pub cid: Cid,
+    pub size: usize,
+}
+
+/// the statistics report of the CAR file.
+/// the block counts and sizes include the duplicate blocks, the block size is the data size
+/// without the cid. the histograms are keyed by the codec and multihash codes.
+#[derive(Debug, Clone, PartialEq, Eq, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+pub struct CarStats {
+    pub version: u64,
+    #[cfg_attr(feature = "serde", serde(serialize_with = "ser::cids"))]
+    pub roots: Vec<Cid>,
+    pub block_count: usize,
+    pub total_size: u64,
+    pub avg_size: u64,
+    pub codecs: BTreeMap<u64, usize>,
+    pub multihashes: BTreeMap<u64, usize>,
+    /// the count of the blocks whose cid is already in the file.
+    pub duplicate_count: usize,
+    /// the count of the distinct blocks which can't be reached from the roots.
+    pub unreachable_count: usize,
+    /// the count of the dag-pb and dag-cbor blocks which can't be decoded or whose hash
+    /// doesn't match when the dag is walked, their links are not followed.
+    pub undecodable_count: usize,
+    /// the largest blocks, in descending order of the size.
+    pub largest_blocks: Vec<BlockStat>,
+}
+
+/// the cids are serialized as the strings.
+#[cfg(feature = "serde")]
+mod ser {
+    use cid::Cid;
+    use serde::Serializer;
+
+    pub(super) fn cid<S: Serializer>(cid: &Cid, s: S) -> Result<S::Ok, S::Error> {
+        s.collect_str(cid)
+    }
+
+    pub(super) fn cids<S: Serializer>(cids: &[Cid], s: S) -> Result<S::Ok, S::Error> {
+        s.collect_seq(cids.iter().map(Cid::to_string))
+    }
+}
+
+/// walk the dag from the roots, return the cids reached in the file
+/// and the count of the blocks which can't be decoded.
+/// the hash mismatch and the decode failures are counted, the other errors are returned.
+/// only the dag-pb and dag-cbor blocks are decoded to follow the links,
+/// the links to the blocks which are not in the file are skipped,
+/// the links of the identity cids are followed, their data is inlined in the cid.
+fn reachable(
+    reader: &mut impl CarReader,
+    present: &HashSet<Cid>,
+) -> Result<(HashSet<Cid>, usize), CarError> {
+    let pb_code: u64 = DagPbCodec.into();
+    let cbor_code: u64 = DagCborCodec.into();
+    let mut reached = HashSet::new();
+    let mut undecodable = 0;
+    let mut queue: VecDeque<Cid> = reader.header().roots().into();
+    while let Some(cid) = queue.pop_front() {
+        let is_present = present.contains(&cid) || identity_data(&cid).is_some();
+        if !is_present || !reached.insert(cid) {
+            continue;
+        }
+        if cid.codec() != pb_code && cid.codec() != cbor_code {
+            continue;
+        }
+        let mut links = Vec::new();
+        match reader.ipld(&cid) {
+            Ok(ipld) => ipld.references(&mut links),
+            Err(
+                CarError::HashMismatch { .. }
+                | CarError::UnsupportedCodec { .. }
+                | CarError::Parsing(_),
+            ) => undecodable += 1,
+            Err(e) => return Err(e),
+        }
+        queue.extend(links);
+    }
+    Ok((reached, undecodable))
+}
+
+/// the statistics of the CAR file by reader, the dag is walked from the roots
+/// to count the unreachable blocks, see `CarStats`.
+#[inline]
+pub fn stats(reader: &mut impl CarReader) -> Result<CarStats, CarError> {
+    stats_with(reader, DEFAULT_LARGEST_BLOCKS)
+}
+
+/// the statistics of the CAR file by reader.
+/// `largest` is the count of the largest blocks in the report.
+pub fn stats_with(reader: &mut impl CarReader, largest: usize) -> Result<CarStats, CarError> {
+    let mut sections = reader.sections();
+    sections.sort_by_key(|s| s.section_pos());
+    let header = reader.header();
+    let mut stats = CarStats {
+        version: header.version(),
+        roots: header.roots(),
+        block_count: sections.len(),
+        ..Default::default()
+    };
+    let mut present = HashSet::new();
+    for s in sections.iter() {
+        let cid = s.cid();
+        stats.total_size += s.len() as u64;
+        *stats.codecs.entry(cid.codec()).or_default() += 1;
+        *stats.multihashes.entry(cid.hash().code()).or_default() += 1;
+        if !present.insert(cid) {
+            stats.duplicate_count += 1;
+        }
+    }
+    if !sections.is_empty() {
+        stats.avg_size = stats.total_size / sections.len() as u64;
+    }
+    let (reached, undecodable) = reachable(reader, &present)?;
+    stats.unreachable_count = present.difference(&reached).count();
+    stats.undecodable_count = undecodable;
+    sections.sort_by_key(|s| Reverse(s.len()));
+    stats.largest_blocks = sections
+        .iter()
+        .take(largest)
+        .map(|s| BlockStat {
+            cid: s.cid(),
+            size: s.len(),
+        })
+        .collect();
+    Ok(stats)
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::{
+        reader,
+        utils::raw_cid,
+        writer::{CarWriter, CarWriterV1},
+    };
+    use std::io::Cursor;
+
+    #[test]
+    fn test_stats() {
+        let file = std::path::Path::new("test");
+        let file = file.join("carv1-basic.car");
+        let data = std::fs::read(file).unwrap();
+        let mut r = reader::new_v1(Cursor::new(&data)).unwrap();
+        let s = stats(&mut r).unwrap();
+        assert_eq!(s.version, 1);
+        assert_eq!(s.roots, r.header().roots());
+        assert_eq!(s.block_count, 6);
+        assert_eq!(s.codecs.values().sum::<usize>(), 6);
+        assert_eq!(s.multihashes.values().sum::<usize>(), 6);
+        assert_eq!(s.duplicate_count, 0);
+        assert_eq!(s.unreachable_count, 0);
+        assert_eq!(s.undecodable_count, 0);
+        assert_eq!(s.largest_blocks.len(), 6);
+        assert!(s.largest_blocks.windows(2).all(|w| w[0].size >= w[1].size));
+        assert_eq!(
+            s.total_size,
+            s.largest_blocks.iter().map(|b| b.size as u64).sum::<u64>()
+        );
+
+        // append a duplicate block and an unreachable block.
+        let sections = r.sections();
+        let mut buf = Vec::new();
+        let mut writer = CarWriterV1::new(Cursor::new(&mut buf), r.header().clone());
+        for sec in sections.iter() {
+            writer
+                .write(sec.cid(), r.read_section_data(&sec.cid()).unwrap())
+                .unwrap();
+        }
+        let dup = sections[0].cid();
+        writer
+            .write(dup, r.read_section_data(&dup).unwrap())
+            .unwrap();
+        writer.write(raw_cid(b"orphan"), b"orphan").unwrap();
+        writer.flush().unwrap();
+        let mut r = reader::new_v1(Cursor::new(&buf)).unwrap();
+        let s = stats_with(&mut r, 2).unwrap();
+        assert_eq!(s.block_count, 8);
+        assert_eq!(s.duplicate_count, 1);
+        assert_eq!(s.unreachable_count, 1);
+        assert_eq!(s.largest_blocks.len(), 2);
+        assert!(s.codecs[&0x55] >= 1);
+
+        #[cfg(feature = "serde")]
+        {
+            let json = serde_json::to_value(&s).unwrap();
+            assert_eq!(json["roots"][0], s.roots[0].to_string());
+            assert_eq!(json["duplicate_count"], 1);
+        }
+    }
+
+    #[test]
+    fn test_stats_undecodable() {
+        let data = std::fs::read("test/carv1-basic.car").unwrap();
+        let mut r = reader::new_v1(Cursor::new(&data)).unwrap();
+        let roots = r.header().roots();
+        // the root is decodable, corrupt the data of the dag-pb or dag-cbor block it links.
+        let mut links = Vec::new();
+        r.ipld(&roots[0]).unwrap().references(&mut links);
+        let corrupt = links
+            .into_iter()
+            .find(|c| c.codec() == 0x70 || c.codec() == 0x71)
+            .unwrap();
+        let mut buf = Vec::new();
+        let mut writer = CarWriterV1::new(Cursor::new(&mut buf), r.header().clone());
+        for sec in r.sections() {
+            let data = match sec.cid() == corrupt {
+                true => vec![0xff; 4],
+                false => r.read_section_data(&sec.cid()).unwrap(),
+            };
+            writer.write(sec.cid(), data).unwrap();
+        }
+        writer.flush().unwrap();
+        let mut r = reader::new_v1(Cursor::new(&buf)).unwrap();
+        let s = stats(&mut r).unwrap();
+        assert_eq!(s.block_count, 6);
+        assert_eq!(s.undecodable_count, 1);
+    }
+
+    /// the reader fails to read the data at `fail_at`, e.g. the disk error.
+    struct FailingReader {
+        inner: Cursor<Vec<u8>>,
+        fail_at: u64,
+    }
+
+    impl std::io::Read for FailingReader {
+        fn read(&mut self, buf: &mut [u8]) -> std::io::Result<usize> {
+            if self.inner.position() == self.fail_at {
+                return Err(std::io::Error::new(std::io::ErrorKind::Other, "disk error"));
+            }
+            self.inner.read(buf)
+        }
+    }
+
+    impl std::io::Seek for FailingReader {
+        fn seek(&mut self, pos: std::io::SeekFrom) -> std::io::Result<u64> {
+            self.inner.seek(pos)
+        }
+    }
+
+    #[test]
+    fn test_stats_read_error() {
+        let data = std::fs::read("test/carv1-basic.car").unwrap();
+        let r = reader::new_v1(Cursor::new(&data)).unwrap();
+        let roots = r.header().roots();
+        let fail_at = r.section(&roots[0]).unwrap().unwrap().pos();
+        drop(r);
+        // the data of the root is read when the dag is walked.
+        let inner = FailingReader {
+            inner: Cursor::new(data),
+            fail_at,
+        };
+        let mut r = reader::new_v1(inner).unwrap();
+        assert!(matches!(stats(&mut r), Err(CarError::IO(_))));
+    }
+}
@@ -3,8 +3,16 @@ use ipld::{pb::DagPbCodec, prelude::Codec};
 
 use crate::{error::CarError, Ipld, CarHeader, utils::{empty_pb_cid, pb_cid}};
 
+#[cfg(feature = "async")]
+mod writer_async;
 mod writer_v1;
+mod writer_v2;
+#[cfg(feature = "async")]
+pub use writer_async::AsyncCarWriter;
+#[cfg(feature = "async")]
+pub(crate) use writer_async::CarWriterAsync;
 pub(crate) use writer_v1::CarWriterV1;
+pub(crate) use writer_v2::CarWriterV2;
 
 pub enum WriteStream<'bs> {
     Bytes(&'bs [u8]),
@@ -43,6 +51,11 @@ pub trait CarWriter {
     fn rewrite_header(&mut self, header: CarHeader) -> Result<(), CarError>;
 
     fn flush(&mut self) -> Result<(), CarError>;
+
+    /// finalize the CAR file, should be called after all blocks and the header are written.
+    fn finalize(&mut self) -> Result<(), CarError> {
+        self.flush()
+    }
 }
 
 pub fn new_v1<W>(inner: W, header: CarHeader) -> Result<impl CarWriter, CarError>
@@ -58,3 +71,20 @@ where
 {
     Ok(CarWriterV1::new(inner, CarHeader::new_v1(vec![empty_pb_cid()])))
 }
+
+/// create the CARv2 writer, the index is written when the writer is finalized.
+pub fn new_v2<W>(inner: W, header: CarHeader) -> Result<impl CarWriter, CarError>
+where
+    W: std::io::Write + std::io::Seek,
+{
+    CarWriterV2::new(inner, header)
+}
+
+/// create the async CARv1 writer, the roots can't be rewritten so they must be provided.
+#[cfg(feature = "async")]
+pub fn new_async<W>(inner: W, header: CarHeader) -> Result<impl AsyncCarWriter, CarError>
+where
+    W: tokio::io::AsyncWrite + Unpin + Send,
+{
+    CarWriterAsync::new(inner, header)
+}
@@ -0,0 +1,157 @@
+use async_trait::async_trait;
+use cid::Cid;
+use integer_encoding::VarIntAsyncWriter;
+use ipld::{pb::DagPbCodec, prelude::Codec};
+use tokio::io::{AsyncWrite, AsyncWriteExt};
+
+use crate::{
+    error::CarError,
+    header::CarHeader,
+    utils::{pb_cid, raw_cid},
+    Ipld,
+};
+
+/// the async counterpart of `CarWriter`.
+/// the async sinks are not seekable, so the header can't be rewritten,
+/// the roots must be known when the writer is created.
+#[async_trait]
+pub trait AsyncCarWriter: Send {
+    async fn write<T>(&mut self, cid: Cid, data: T) -> Result<(), CarError>
+    where
+        T: AsRef<[u8]> + Send;
+
+    async fn write_ipld(&mut self, ipld: Ipld) -> Result<Cid, CarError> {
+        match ipld {
+            Ipld::Bytes(buf) => {
+                let file_cid = raw_cid(&buf);
+                self.write(file_cid, &buf).await?;
+                Ok(file_cid)
+            }
+            fs_ipld @ Ipld::Map(_) => {
+                let bs: Vec<u8> = DagPbCodec
+                    .encode(&fs_ipld)
+                    .map_err(|e| CarError::Parsing(e.to_string()))?;
+                let cid = pb_cid(&bs);
+                self.write(cid, &bs).await?;
+                Ok(cid)
+            }
+            _ => Err(CarError::Parsing("Not support write ipld.".to_lowercase())),
+        }
+    }
+
+    async fn flush(&mut self) -> Result<(), CarError>;
+
+    /// finalize the CAR file, the header is written if no block is written.
+    /// the sink is flushed but not shut down.
+    async fn finalize(&mut self) -> Result<(), CarError>;
+}
+
+/// the async CARv1 writer, the header is written before the first block.
+pub(crate) struct CarWriterAsync<W> {
+    inner: W,
+    header: CarHeader,
+    is_header_written: bool,
+}
+
+impl<W> CarWriterAsync<W>
+where
+    W: AsyncWrite + Unpin + Send,
+{
+    pub(crate) fn new(inner: W, header: CarHeader) -> Result<Self, CarError> {
+        if let CarHeader::V2(_) = header {
+            return Err(CarError::InvalidFile(
+                "the async writer only support CARv1".into(),
+            ));
+        }
+        if header.roots().is_empty() {
+            return Err(CarError::InvalidFile(
+                "the roots must be provided to the async writer".into(),
+            ));
+        }
+        Ok(Self {
+            inner,
+            header,
+            is_header_written: false,
+        })
+    }
+
+    async fn write_head(&mut self) -> Result<(), CarError> {
+        let head = self.header.encode()?;
+        self.inner.write_varint_async(head.len()).await?;
+        self.inner.write_all(&head).await?;
+        self.is_header_written = true;
+        Ok(())
+    }
+}
+
+#[async_trait]
+impl<W> AsyncCarWriter for CarWriterAsync<W>
+where
+    W: AsyncWrite + Unpin + Send,
+{
+    async fn write<T>(&mut self, cid: Cid, data: T) -> Result<(), CarError>
+    where
+        T: AsRef<[u8]> + Send,
+    {
+        if !self.is_header_written {
+            self.write_head().await?;
+        }
+        let cid_buff = cid.to_bytes();
+        let data = data.as_ref();
+        let sec_len = data.len() + cid_buff.len();
+        self.inner.write_varint_async(sec_len).await?;
+        self.inner.write_all(&cid_buff[..]).await?;
+        self.inner.write_all(data).await?;
+        Ok(())
+    }
+
+    async fn flush(&mut self) -> Result<(), CarError> {
+        self.inner.flush().await?;
+        Ok(())
+    }
+
+    async fn finalize(&mut self) -> Result<(), CarError> {
+        if !self.is_header_written {
+            self.write_head().await?;
+        }
+        self.flush().await
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::writer::{CarWriter, CarWriterV1};
+    use futures::executor::block_on;
+    use std::io::Cursor;
+
+    #[test]
+    fn test_async_writer() {
+        let cid1 = raw_cid(b"test1");
+        let cid2 = raw_cid(b"test2");
+        let header = CarHeader::new_v1(vec![cid2]);
+        let mut expected = Vec::new();
+        let mut writer = CarWriterV1::new(Cursor::new(&mut expected), header.clone());
+        writer.write(cid1, b"test1").unwrap();
+        writer.write(cid2, b"test2").unwrap();
+        writer.flush().unwrap();
+
+        let mut buffer = Vec::new();
+        block_on(async {
+            let mut writer = CarWriterAsync::new(&mut buffer, header.clone()).unwrap();
+            writer.write(cid1, b"test1").await.unwrap();
+            assert_eq!(
+                writer
+                    .write_ipld(Ipld::Bytes(b"test2".to_vec()))
+                    .await
+                    .unwrap(),
+                cid2
+            );
+            writer.finalize().await.unwrap();
+        });
+        assert_eq!(buffer, expected);
+
+        assert!(CarWriterAsync::new(Vec::new(), CarHeader::new_v1(vec![])).is_err());
+        assert!(CarWriterAsync::new(Vec::new(), CarHeader::new_v2(vec![cid1])).is_err());
+    }
+}
@@ -31,6 +31,20 @@ where
             is_header_written: false,
         }
     }
+
+    /// continue writing the sections after the header which is already written.
+    pub(crate) fn resume(inner: W, header: CarHeader) -> Self {
+        Self {
+            inner,
+            header,
+            is_header_written: true,
+        }
+    }
+
+    #[inline(always)]
+    pub(crate) fn inner_mut(&mut self) -> &mut W {
+        &mut self.inner
+    }
 }
 
 impl<W> CarWriter for CarWriterV1<W>
@@ -0,0 +1,236 @@
+use std::io::{self, Seek, SeekFrom, Write};
+
+use cid::Cid;
+
+use super::{CarWriter, CarWriterV1, WriteStream};
+use crate::{
+    error::CarError,
+    header::{CarHeader, CarHeaderV2},
+    index::{write_index, Index},
+    section::Section,
+};
+
+/// the writer of the CARv1 data payload, the positions are relative to the payload start.
+struct OffsetWriter<W> {
+    inner: W,
+    base: u64,
+}
+
+impl<W> OffsetWriter<W> {
+    #[inline(always)]
+    fn get_mut(&mut self) -> &mut W {
+        &mut self.inner
+    }
+}
+
+impl<W: Write> Write for OffsetWriter<W> {
+    #[inline(always)]
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        self.inner.write(buf)
+    }
+
+    #[inline(always)]
+    fn flush(&mut self) -> io::Result<()> {
+        self.inner.flush()
+    }
+}
+
+impl<W: Seek> Seek for OffsetWriter<W> {
+    fn seek(&mut self, pos: SeekFrom) -> io::Result<u64> {
+        let pos = match pos {
+            SeekFrom::Start(n) => SeekFrom::Start(self.base + n),
+            pos => pos,
+        };
+        let n = self.inner.seek(pos)?;
+        n.checked_sub(self.base).ok_or_else(|| {
+            io::Error::new(io::ErrorKind::InvalidInput, "seek before the data payload")
+        })
+    }
+}
+
+/// the CARv2 writer, the data payload is written by the CARv1 writer,
+/// the index is appended and the header is filled when the writer is finalized.
+pub(crate) struct CarWriterV2<W> {
+    inner: CarWriterV1<OffsetWriter<W>>,
+    header: CarHeaderV2,
+    sections: Vec<Section>,
+    data_size: u64,
+}
+
+impl<W> CarWriterV2<W>
+where
+    W: std::io::Write + std::io::Seek,
+{
+    pub fn new(mut inner: W, header: CarHeader) -> Result<Self, CarError> {
+        let header = match header {
+            CarHeader::V1(v1) => CarHeaderV2::new(v1),
+            CarHeader::V2(v2) => v2,
+        };
+        // the header is filled when finalized, keep the space for it.
+        let head = header.encode();
+        inner.write_all(&head)?;
+        let padding = header.data_offset.checked_sub(head.len() as u64).ok_or(
+            CarError::InvalidFile("the data offset overlaps the header".into()),
+        )?;
+        inner.write_all(&vec![0u8; padding as usize])?;
+        let inner = OffsetWriter {
+            inner,
+            base: header.data_offset,
+        };
+        let v1_header = CarHeader::V1(header.inner.clone());
+        Ok(Self {
+            inner: CarWriterV1::new(inner, v1_header),
+            header,
+            sections: Vec::new(),
+            data_size: 0,
+        })
+    }
+
+    /// resume the unfinalized file, the sections are appended after the data payload,
+    /// the positions of the written sections are relative to the data payload.
+    pub(crate) fn resume(
+        mut inner: W,
+        header: CarHeaderV2,
+        sections: Vec<Section>,
+        data_size: u64,
+    ) -> Result<Self, CarError> {
+        inner.seek(SeekFrom::Start(header.data_offset + data_size))?;
+        let inner = OffsetWriter {
+            inner,
+            base: header.data_offset,
+        };
+        let v1_header = CarHeader::V1(header.inner.clone());
+        Ok(Self {
+            inner: CarWriterV1::resume(inner, v1_header),
+            header,
+            sections,
+            data_size,
+        })
+    }
+
+    /// the underlying writer, the position must be restored after it's used.
+    #[inline(always)]
+    pub(crate) fn get_mut(&mut self) -> &mut W {
+        self.inner.inner_mut().get_mut()
+    }
+
+    /// record the section which ends at the current position.
+    fn record_section(&mut self, cid: Cid, len: usize) -> Result<(), CarError> {
+        let end = self.inner.inner_mut().stream_position()?;
+        self.sections.push(Section::new(cid, end - len as u64, len));
+        self.data_size = self.data_size.max(end);
+        Ok(())
+    }
+}
+
+impl<W> CarWriter for CarWriterV2<W>
+where
+    W: std::io::Write + std::io::Seek,
+{
+    fn write<T>(&mut self, cid: Cid, data: T) -> Result<(), CarError>
+    where
+        T: AsRef<[u8]>,
+    {
+        let data = data.as_ref();
+        self.inner.write(cid, data)?;
+        self.record_section(cid, data.len())
+    }
+
+    fn write_stream<F, R>(
+        &mut self,
+        cid_f: F,
+        stream_len: usize,
+        r: &mut R,
+    ) -> Result<Cid, CarError>
+    where
+        R: std::io::Read,
+        F: FnMut(WriteStream) -> Option<Result<Cid, CarError>>,
+    {
+        let cid = self.inner.write_stream(cid_f, stream_len, r)?;
+        self.record_section(cid, stream_len)?;
+        Ok(cid)
+    }
+
+    fn rewrite_header(&mut self, header: CarHeader) -> Result<(), CarError> {
+        let v1 = match header {
+            CarHeader::V1(v1) => v1,
+            CarHeader::V2(v2) => v2.inner,
+        };
+        self.inner.rewrite_header(CarHeader::V1(v1.clone()))?;
+        self.header.inner = v1;
+        let pos = self.inner.inner_mut().stream_position()?;
+        self.data_size = self.data_size.max(pos);
+        Ok(())
+    }
+
+    fn flush(&mut self) -> Result<(), CarError> {
+        self.inner.flush()
+    }
+
+    fn finalize(&mut self) -> Result<(), CarError> {
+        if self.data_size == 0 {
+            // nothing is written, write the header of the empty payload.
+            self.rewrite_header(CarHeader::V1(self.header.inner.clone()))?;
+        }
+        // the positions of the sections are relative to the data payload.
+        let codec = self.header.index_codec.unwrap_or_default();
+        let index = Index::from_sections(codec, self.sections.iter(), 0);
+        self.header.index_codec = Some(codec);
+        self.header.data_size = self.data_size;
+        self.header.index_offset = self.header.data_offset + self.data_size;
+        self.header.characteristics.set_fully_indexed(true);
+        let w = self.inner.inner_mut().get_mut();
+        w.seek(SeekFrom::Start(self.header.index_offset))?;
+        write_index(&mut *w, &index)?;
+        w.seek(SeekFrom::Start(0))?;
+        w.write_all(&self.header.encode())?;
+        w.flush()?;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use std::io::Cursor;
+
+    use super::*;
+    use crate::index::IndexCodec;
+    use crate::reader::{CarReader, CarReaderV2};
+    use crate::utils::{pb_cid, raw_cid};
+
+    #[test]
+    fn test_writer_read_v2() {
+        write_read_v2(IndexCodec::MultihashIndexSorted);
+        write_read_v2(IndexCodec::IndexSorted);
+    }
+
+    fn write_read_v2(codec: IndexCodec) {
+        let cid_test1 = raw_cid(b"test1");
+        let cid_test2 = raw_cid(b"test2");
+        let mut header = CarHeaderV2::new(Default::default());
+        header.inner.roots = vec![pb_cid(b"root")];
+        header.index_codec = Some(codec);
+        let header = CarHeader::V2(header);
+        let mut buffer = Vec::new();
+        let mut writer = CarWriterV2::new(Cursor::new(&mut buffer), header).unwrap();
+        writer.write(cid_test1, b"test1").unwrap();
+        writer.write(cid_test2, b"test2").unwrap();
+        writer
+            .rewrite_header(CarHeader::new_v1(vec![cid_test2]))
+            .unwrap();
+        writer.finalize().unwrap();
+        let mut car_reader = CarReaderV2::new(Cursor::new(&buffer)).unwrap();
+        match car_reader.header() {
+            CarHeader::V2(v2) => {
+                assert!(v2.has_index());
+                assert!(v2.characteristics.is_fully_indexed());
+                assert_eq!(v2.index_codec, Some(codec));
+            }
+            CarHeader::V1(_) => panic!("should be the CARv2 header"),
+        }
+        assert_eq!(vec![cid_test2], car_reader.header().roots());
+        assert_eq!(car_reader.sections().len(), 2);
+        assert_eq!(car_reader.read_section_data(&cid_test1).unwrap(), b"test1");
+        assert_eq!(car_reader.read_section_data(&cid_test2).unwrap(), b"test2");
+    }
+}